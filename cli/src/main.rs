@@ -1,7 +1,9 @@
 use clap::{command, Arg, ArgAction, Command, Parser, ValueEnum};
-use clio::Output;
+use clio::{Input, Output};
 use std::fmt::{Display, Formatter};
-use std::io::Write;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use sysbadge::system::downloaders::{Progress, RetryPolicy, StatusPolicy};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -17,12 +19,61 @@ struct Cli {
 #[derive(clap::Subcommand)]
 enum Commands {
     Dl {
-        #[clap()]
-        id: String,
+        /// System ids, optionally prefixed with their source like `pk:abcde`.
+        ///
+        /// Several systems are stored together, the badge can switch between them.
+        #[clap(required_unless_present = "file")]
+        ids: Vec<String>,
+
+        /// Read a system from a file instead, like the one written by `pk;export`.
+        #[clap(long, value_parser)]
+        file: Vec<Input>,
 
         #[clap(long, short, default_value = "PluralKit")]
         source: sysbadge::system::downloaders::Source,
 
+        /// Base url of a self-hosted PluralKit API.
+        #[clap(long, value_parser)]
+        pk_base_url: Option<String>,
+
+        /// Base url of a self-hosted pronouns.cc API.
+        #[clap(long, value_parser)]
+        pronouns_base_url: Option<String>,
+
+        /// PluralKit token of the system, from `pk;token`. Private members are only kept if
+        /// allowed with `--allow-private`.
+        #[clap(long, value_parser, env = "PLURALKIT_TOKEN", hide_env_values = true)]
+        pk_token: Option<String>,
+
+        /// Start the badge on the members currently fronting in PluralKit.
+        #[clap(long)]
+        with_front: bool,
+
+        /// Leave out the avatars, saving a request per member and 512 bytes of flash each.
+        #[clap(long)]
+        no_avatars: bool,
+
+        /// Id of a private member or group to keep on the badge anyway, with its private fields.
+        #[clap(long, value_parser)]
+        allow_private: Vec<String>,
+
+        /// pronouns.cc statuses kept on the badge, most preferred first.
+        #[clap(
+            long,
+            value_parser,
+            value_delimiter = ',',
+            default_value = "favourite,okay"
+        )]
+        pronoun_statuses: Vec<String>,
+
+        /// pronouns.cc pronoun sets shown together on the badge.
+        #[clap(long, value_parser, default_value = "1")]
+        shown_pronouns: usize,
+
+        /// Retries of failed requests, with growing pauses in between.
+        #[clap(long, value_parser, default_value = "4")]
+        retries: u32,
+
         /// Output format.
         #[clap(long, short, value_parser, default_value = "uf2")]
         format: DlFormat,
@@ -33,9 +84,37 @@ enum Commands {
         /// Output file '-' for stdout
         #[clap(long, short, value_parser)]
         output: Option<Output>,
+
+        /// JSON of an earlier download, members keep their order and a summary of the changes
+        /// is printed. The merged systems are written back to it.
+        #[clap(long, value_parser)]
+        merge_with: Option<PathBuf>,
+
+        /// Local edits applied to the download, defaults to `<merge-with>.overrides.json`.
+        #[clap(long, value_parser, requires = "merge_with")]
+        overrides: Option<PathBuf>,
+    },
+    /// Print the systems stored in a .uf2 or .bin image.
+    Inspect {
+        /// Image file, '-' for stdin
+        #[clap(value_parser)]
+        input: Input,
+
+        #[clap(long, short, value_parser, default_value = "table")]
+        format: InspectFormat,
+
+        /// Address of the data region in .uf2 images.
+        #[clap(long, value_parser, default_value = "270467072")]
+        offset: u32,
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum InspectFormat {
+    Table,
+    Json,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 enum DlFormat {
     UF2,
@@ -59,30 +138,305 @@ async fn main() {
 
     match &cli.command {
         Some(Commands::Dl {
-            id,
+            ids,
+            file,
             source,
+            pk_base_url,
+            pronouns_base_url,
+            pk_token,
+            with_front,
+            no_avatars,
+            allow_private,
+            pronoun_statuses,
+            shown_pronouns,
+            retries,
             format,
             offset,
             output,
+            merge_with,
+            overrides,
         }) => {
-            let mut downloader = sysbadge::system::downloaders::GenericDownloader::new();
+            if ids.len() + file.len() > sysbadge::system::MAX_SYSTEMS {
+                eprintln!(
+                    "The badge holds at most {} systems",
+                    sysbadge::system::MAX_SYSTEMS
+                );
+                std::process::exit(1);
+            }
+
+            let mut downloader = sysbadge::system::downloaders::GenericDownloader::new()
+                .with_retry(RetryPolicy {
+                    max_retries: *retries,
+                    ..Default::default()
+                })
+                .with_progress(print_progress);
             downloader.useragent = "SysBadge CLI".to_string();
-            let mut system = downloader.get(*source, id).await.unwrap();
-            system.sort_members();
+            downloader.pk_base_url = pk_base_url.clone();
+            downloader.pronouns_base_url = pronouns_base_url.clone();
+            downloader.pk_token = pk_token.clone();
+            downloader.pk_front = *with_front;
+            downloader.avatars = !*no_avatars;
+            downloader.pronouns_policy = StatusPolicy {
+                statuses: pronoun_statuses.clone(),
+                shown_pronouns: *shown_pronouns,
+            };
+            downloader.privacy = sysbadge::system::PrivacyPolicy::with_allowed(allow_private);
+            let mut systems = Vec::with_capacity(ids.len() + file.len());
+            for input in file {
+                let mut data = Vec::new();
+                input.clone().read_to_end(&mut data).unwrap();
+                let mut system = downloader.import(*source, &data).unwrap_or_else(|err| {
+                    eprintln!("Failed to read {}: {}", input.path().display(), err);
+                    std::process::exit(1);
+                });
+                system.sort_members();
+                systems.push(system);
+            }
+            for id in ids {
+                let (source, id) = id
+                    .split_once(':')
+                    .and_then(|(prefix, id)| Some((prefix.parse().ok()?, id)))
+                    .unwrap_or((*source, id.as_str()));
+                let mut system = downloader.get(source, id).await.unwrap_or_else(|err| {
+                    eprintln!("Failed to download {} from {}: {}", id, source, err);
+                    std::process::exit(1);
+                });
+                system.sort_members();
+                systems.push(system);
+            }
+
+            if let Some(path) = merge_with {
+                let overrides = overrides
+                    .clone()
+                    .unwrap_or_else(|| path.with_extension("overrides.json"));
+                systems = merge(systems, path, &overrides);
+                std::fs::write(path, to_json(&systems)).unwrap_or_else(|err| {
+                    eprintln!("Failed to write {}: {}", path.display(), err);
+                    std::process::exit(1);
+                });
+            }
 
             let mut output = match output {
                 Some(output) => output.clone(),
-                None => Output::new(&format!("{}.{}", system.name, format)).unwrap(),
+                None => Output::new(&format!("{}.{}", systems[0].name, format)).unwrap(),
             };
 
             let data = match format {
-                DlFormat::UF2 => system.get_uf2(*offset),
-                DlFormat::Bin => system.get_bin(),
-                DlFormat::Json => serde_json::ser::to_vec_pretty(&system).unwrap(),
+                DlFormat::UF2 => sysbadge::system::SystemVec::get_uf2_multi(&systems, *offset),
+                DlFormat::Bin => sysbadge::system::SystemVec::get_bin_multi(&systems),
+                DlFormat::Json => Ok(to_json(&systems)),
             };
+            let data = data.unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            });
 
             output.write_all(&data).unwrap();
         }
+        Some(Commands::Inspect {
+            input,
+            format,
+            offset,
+        }) => {
+            let mut data = Vec::new();
+            input.clone().read_to_end(&mut data).unwrap();
+
+            let systems = if data.starts_with(b"UF2\n") {
+                sysbadge::system::SystemVec::from_uf2(&data, *offset).map_err(|err| err.to_string())
+            } else {
+                sysbadge::system::SystemVec::from_bin_multi(&data).map_err(|err| err.to_string())
+            };
+            let systems = systems.unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            });
+
+            match format {
+                InspectFormat::Json => {
+                    println!("{}", serde_json::ser::to_string_pretty(&systems).unwrap())
+                }
+                InspectFormat::Table => {
+                    for system in &systems {
+                        print_system(system);
+                    }
+                }
+            }
+        }
         _ => todo!(),
     }
 }
+
+fn print_progress(progress: &Progress) {
+    match progress {
+        Progress::Retry {
+            url,
+            attempt,
+            delay_ms,
+            error,
+        } => eprintln!(
+            "{} failed ({}), retry {} in {:.1}s",
+            url,
+            error,
+            attempt,
+            *delay_ms as f32 / 1000.0
+        ),
+        Progress::Avatars { done, total } => eprint!(
+            "\rDownloaded {}/{} avatars{}",
+            done,
+            total,
+            if done == total { "\n" } else { "" }
+        ),
+        Progress::Skipped { system, names } => eprintln!(
+            "Skipped {} private members of {}: {}",
+            names.len(),
+            system,
+            names.join(", ")
+        ),
+        _ => {}
+    }
+}
+
+/// Merges the downloaded `systems` into the ones stored at `path`, printing what changed.
+fn merge(
+    systems: Vec<sysbadge::system::SystemVec>,
+    path: &Path,
+    overrides: &Path,
+) -> Vec<sysbadge::system::SystemVec> {
+    use sysbadge::system::{Overrides, SystemVec};
+
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        One(SystemVec),
+        Many(Vec<SystemVec>),
+    }
+
+    let read = |path: &Path| {
+        std::fs::read(path).unwrap_or_else(|err| {
+            eprintln!("Failed to read {}: {}", path.display(), err);
+            std::process::exit(1);
+        })
+    };
+    let existing = match serde_json::from_slice(&read(path)) {
+        Ok(Stored::One(system)) => vec![system],
+        Ok(Stored::Many(systems)) => systems,
+        Err(err) => {
+            eprintln!("Invalid system in {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    };
+    let overrides: Overrides = if overrides.exists() {
+        serde_json::from_slice(&read(overrides)).unwrap_or_else(|err| {
+            eprintln!("Invalid overrides in {}: {}", overrides.display(), err);
+            std::process::exit(1);
+        })
+    } else {
+        Overrides::default()
+    };
+
+    systems
+        .into_iter()
+        .enumerate()
+        .map(|(index, upstream)| {
+            let mut system = existing
+                .iter()
+                .find(|old| old.source_id.is_some() && old.source_id == upstream.source_id)
+                .or_else(|| existing.get(index))
+                .cloned()
+                .unwrap_or_else(|| SystemVec::new(String::new()));
+            let summary = system.merge(upstream, &overrides);
+            eprint!("{}:\n{}", system.name, summary);
+            system
+        })
+        .collect()
+}
+
+/// Pretty JSON of `systems`, a single system is written on its own as read by [`merge`].
+fn to_json(systems: &[sysbadge::system::SystemVec]) -> Vec<u8> {
+    match systems {
+        [system] => serde_json::ser::to_vec_pretty(system).unwrap(),
+        systems => serde_json::ser::to_vec_pretty(systems).unwrap(),
+    }
+}
+
+fn print_system(system: &sysbadge::system::SystemVec) {
+    use sysbadge::system::SourceId;
+
+    println!("System: {}", system.name);
+    match &system.source_id {
+        Some(SourceId::PluralKit(id)) => println!("PluralKit: {}", id),
+        Some(SourceId::Pronouns(id)) => println!("pronouns.cc: {}", id),
+        Some(SourceId::SimplyPlural(id)) => println!("Simply Plural: {}", id),
+        Some(SourceId::Tupperbox(id)) => println!("Tupperbox: {}", id),
+        None => {}
+    }
+    if let Some(url) = &system.url {
+        println!("Url: {}", url);
+    }
+    if !system.front.is_empty() {
+        let front: Vec<&str> = system
+            .front
+            .iter()
+            .filter_map(|&index| system.members.get(index as usize))
+            .map(|member| member.shown_name())
+            .collect();
+        println!("Front: {}", front.join(", "));
+    }
+    println!();
+
+    let rows: Vec<[String; 6]> = system
+        .members
+        .iter()
+        .enumerate()
+        .map(|(index, member)| {
+            [
+                index.to_string(),
+                member.shown_name().to_string(),
+                member.pronouns.clone(),
+                member.id.clone().unwrap_or_default(),
+                member
+                    .birthday
+                    .map(|birthday| birthday.to_string())
+                    .unwrap_or_default(),
+                member
+                    .color
+                    .map(|color| format!("#{:06x}", color))
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_table(["#", "Name", "Pronouns", "Id", "Birthday", "Colour"], &rows);
+
+    for group in &system.groups {
+        let members: Vec<_> = group
+            .members
+            .iter()
+            .filter_map(|&index| system.members.get(index as usize))
+            .map(|member| member.shown_name())
+            .collect();
+        println!("Group {}: {}", group.name, members.join(", "));
+    }
+    println!();
+}
+
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(|title| title.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: [&str; N]| {
+        let line: Vec<_> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+    print_row(header);
+    for row in rows {
+        print_row(row.each_ref().map(String::as_str));
+    }
+}
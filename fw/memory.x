@@ -1,13 +1,16 @@
 MEMORY {
-    BOOT2 : ORIGIN = 0x10000000, LENGTH = 0x100
-    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
-    RAM   : ORIGIN = 0x20000000, LENGTH = 256K
+    BOOT2    : ORIGIN = 0x10000000, LENGTH = 0x100
+    FLASH    : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100 - 4K - 64K
+    /* settings like the selected system, one erase sector below the systems */
+    SETTINGS : ORIGIN = 0x10000000 + 2048K - 64K - 4K, LENGTH = 4K
+    /* ### HAS TO BE KEPT IN SYNC WITH THE EXPORTER */
+    SYSTEM   : ORIGIN = 0x10000000 + 2048K - 64K, LENGTH = 64K
+    RAM      : ORIGIN = 0x20000000, LENGTH = 256K
 }
 
-/* ### HAS TO BE KEPT IN SYNC WITH THE EXPORTER */
-/* __ssystem_start = ORIGIN(FLASH) + 0x40000 - 0x100; */
-__ssystem_start = ORIGIN(FLASH) + LENGTH(FLASH) - 64K;
-__ssystem_end = __ssystem_start + 64K;
+__ssystem_start = ORIGIN(SYSTEM);
+__ssystem_end = ORIGIN(SYSTEM) + LENGTH(SYSTEM);
+__ssettings_start = ORIGIN(SETTINGS);
 
 EXTERN(BOOT2_FIRMWARE)
 
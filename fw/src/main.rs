@@ -2,6 +2,7 @@
 #![no_main]
 #![feature(type_alias_impl_trait)]
 
+mod settings;
 mod usb;
 
 use defmt::*;
@@ -24,9 +25,10 @@ use panic_probe as _;
 
 use uc8151::Uc8151;
 
-use sysbadge::badge::Sysbadge;
-use sysbadge::system::SystemReader;
-use sysbadge::Button;
+use sysbadge::badge::{Refresh, Sysbadge};
+use sysbadge::system::capnp::Word;
+use sysbadge::system::{Systems, UNPACK_BUFFER_SIZE};
+use sysbadge::{Button, System};
 
 pub enum UsbControl {
     GetMemberCount,
@@ -59,9 +61,17 @@ type SysbadgeUc8151<'a> = Sysbadge<
         Input<'a, peripherals::PIN_26>,
         Output<'a, peripherals::PIN_21>,
     >,
-    SystemReader<sysbadge::system::capnp::serialize::NoAllocSliceSegments<'a>>,
+    Systems<'a>,
 >;
 
+/// Packed systems are unpacked into this buffer on boot.
+///
+/// Without an allocator it is sized for the largest system the exporters accept, see
+/// [`UNPACK_BUFFER_SIZE`]. The other statics, the display buffer and the stacks stay well below
+/// the remaining 160K of RAM.
+static mut SYSTEM_BUFFER: [Word; UNPACK_BUFFER_SIZE / 8] =
+    [sysbadge::system::capnp::word(0, 0, 0, 0, 0, 0, 0, 0); UNPACK_BUFFER_SIZE / 8];
+
 const SERIAL_LEN: usize = 16;
 static mut SERIAL: [u8; SERIAL_LEN] = [0; 16];
 
@@ -137,7 +147,14 @@ fn init(p: Peripherals) -> SysbadgeUc8151<'static> {
         "Failed to setup display"
     );
 
-    let system = unsafe { sysbadge::system::SystemReader::from_linker_symbols() };
+    let buffer = unsafe { &mut *core::ptr::addr_of_mut!(SYSTEM_BUFFER) };
+    let system = unsafe { Systems::from_linker_symbols(buffer) };
+    if let Some(index) = settings::selected_system() {
+        system.select_system(index as usize);
+    }
+    if let Some(err) = system.error() {
+        warn!("Invalid system: {}", err);
+    }
     let mut sysbadge = Sysbadge::new(display, system);
 
     info!("updating display");
@@ -169,6 +186,7 @@ async fn core0_init(
         badge.lock().await.serial = Some(serial);
     }
 
+    spawner.spawn(settings::settings_task(flash)).unwrap();
     spawner.spawn(button_task_a()).unwrap();
     spawner.spawn(button_task_b()).unwrap();
     spawner.spawn(button_task_c()).unwrap();
@@ -198,10 +216,7 @@ async fn update_redraw_timer_task(
     'outer: loop {
         let button = CHANNEL.receive().await;
         //let badge = unsafe { unwrap!(SYSBADGE.as_mut()) };
-        {
-            let mut badge = badge.lock().await;
-            badge.press(button);
-        }
+        press(&mut *badge.lock().await, button);
         loop {
             let ret = select(
                 CHANNEL.receive(),
@@ -210,13 +225,13 @@ async fn update_redraw_timer_task(
             .await;
             match ret {
                 embassy_futures::select::Either::First(btn) => {
-                    let mut badge = badge.lock().await;
-                    badge.press(btn);
+                    press(&mut *badge.lock().await, btn);
                 }
                 embassy_futures::select::Either::Second(_) => {
                     let mut badge = badge.lock().await;
-                    unwrap!(badge.draw());
-                    unwrap!(badge.display.update(), "Failed to update display");
+                    if unwrap!(badge.draw()) {
+                        update_display(&mut badge);
+                    }
                     continue 'outer;
                 }
             }
@@ -224,6 +239,39 @@ async fn update_redraw_timer_task(
     }
 }
 
+/// Passes `button` to the badge and persists a newly selected system.
+fn press(badge: &mut SysbadgeUc8151<'static>, button: Button) {
+    let selected = badge.system.selected_system();
+    badge.press(button);
+    if badge.system.selected_system() != selected {
+        settings::SELECTED_SYSTEM.signal(badge.system.selected_system() as u8);
+    }
+}
+
+/// Refreshes the panel, limited to the dirty regions of the last draw if possible.
+fn update_display(badge: &mut SysbadgeUc8151<'static>) {
+    match badge.refresh() {
+        Refresh::Full => unwrap!(badge.display.update(), "Failed to update display"),
+        Refresh::Partial(regions) => {
+            for region in regions.iter() {
+                // the controller addresses rows in blocks of 8 pixels
+                let y = region.top_left.y.max(0) as u32 & !7;
+                let bottom = region.top_left.y.max(0) as u32 + region.size.height;
+                let region = uc8151::UpdateRegion {
+                    x: region.top_left.x.max(0) as u32,
+                    y,
+                    width: region.size.width,
+                    height: (bottom - y + 7) & !7,
+                };
+                unwrap!(
+                    badge.display.partial_update(region),
+                    "Failed to update display region"
+                );
+            }
+        }
+    }
+}
+
 const DELAY: u64 = 250;
 #[embassy_executor::task]
 async fn button_task_a() {
@@ -0,0 +1,55 @@
+//! Settings kept in the flash sector below the system data, so they survive a reboot.
+
+use defmt::*;
+use embassy_rp::flash::ERASE_SIZE;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+
+use crate::RpFlashMutex;
+
+const FLASH_BASE: u32 = 0x1000_0000;
+const MAGIC: [u8; 4] = *b"SBst";
+/// Smallest unit the flash can be programmed in.
+const PAGE_SIZE: usize = 256;
+
+/// Index of the shown system, written to flash by [`settings_task`].
+pub static SELECTED_SYSTEM: Signal<CriticalSectionRawMutex, u8> = Signal::new();
+
+/// Index of the system shown before the last reboot.
+pub fn selected_system() -> Option<u8> {
+    let bytes = unsafe { settings_bytes() };
+    (bytes[..MAGIC.len()] == MAGIC).then_some(bytes[MAGIC.len()])
+}
+
+#[embassy_executor::task]
+pub async fn settings_task(flash: &'static RpFlashMutex<'static>) {
+    loop {
+        let index = SELECTED_SYSTEM.wait().await;
+        if selected_system() == Some(index) {
+            continue;
+        }
+
+        let offset = unsafe { &__ssettings_start as *const u8 as u32 } - FLASH_BASE;
+        let mut page = [0xff; PAGE_SIZE];
+        page[..MAGIC.len()].copy_from_slice(&MAGIC);
+        page[MAGIC.len()] = index;
+
+        let mut flash = flash.lock().await;
+        if let Err(err) = flash.blocking_erase(offset, offset + ERASE_SIZE as u32) {
+            warn!("Failed to erase settings: {}", err);
+            continue;
+        }
+        if let Err(err) = flash.blocking_write(offset, &page) {
+            warn!("Failed to write settings: {}", err);
+        }
+        debug!("Stored selected system {}", index);
+    }
+}
+
+extern "C" {
+    static __ssettings_start: u8;
+}
+
+unsafe fn settings_bytes() -> &'static [u8] {
+    unsafe { core::slice::from_raw_parts(&__ssettings_start as *const u8, MAGIC.len() + 1) }
+}
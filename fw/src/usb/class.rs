@@ -9,9 +9,10 @@ use embassy_usb::types::InterfaceNumber;
 use embassy_usb::{Builder, Handler};
 
 use crate::{RpFlashMutex, SERIAL_LEN};
+use sysbadge::badge::{CurrentMenu, STATE_MAX_LEN};
 use sysbadge::system::Member;
 use sysbadge::usb::BootSel::Application;
-use sysbadge::{badge::CurrentMenu, usb as sysusb, System};
+use sysbadge::{usb as sysusb, System};
 
 pub struct State {
     control: MaybeUninit<Control>,
@@ -98,27 +99,30 @@ impl Handler for Control {
                 }
                 Err(_) => OutResponse::Rejected,
             }),
-            Ok(sysusb::Request::SetState)
-                if req.length == core::mem::size_of::<CurrentMenu>() as u16 =>
-            {
+            Ok(sysusb::Request::SetState) => {
                 debug!("Received state");
-                defmt::assert!(data.len() >= core::mem::size_of::<CurrentMenu>());
 
-                let state = CurrentMenu::from_bytes(data);
-
-                block_on(async {
-                    let mut badge = self.badge.lock().await;
-                    badge.set_current(state);
+                let state = CurrentMenu::decode(data).and_then(|state| {
+                    block_on(async {
+                        let mut badge = self.badge.lock().await;
+                        badge.set_current(state)
+                    })
                 });
-
-                Some(OutResponse::Accepted)
+                Some(match state {
+                    Ok(()) => OutResponse::Accepted,
+                    Err(e) => {
+                        warn!("Rejected state: {}", e);
+                        OutResponse::Rejected
+                    }
+                })
             }
             Ok(sysusb::Request::UpdateDisplay) => {
                 debug!("Received update display");
                 block_on(async {
                     let mut badge = self.badge.lock().await;
-                    unwrap!(badge.draw());
-                    unwrap!(badge.display.update(), "Failed to update display");
+                    if unwrap!(badge.draw()) {
+                        crate::update_display(&mut badge);
+                    }
                 });
                 Some(OutResponse::Accepted)
             }
@@ -200,9 +204,7 @@ impl Handler for Control {
                     } else {
                         let member = badge.system.member(offset);
                         let name = member.name();
-                        let name = name.as_bytes();
-                        buf[..name.len()].copy_from_slice(name);
-                        Some(InResponse::Accepted(&buf[..name.len()]))
+                        Some(InResponse::Accepted(copy_truncated(buf, &name)))
                     }
                 })
             }
@@ -218,26 +220,55 @@ impl Handler for Control {
                     } else {
                         let member = badge.system.member(offset);
                         let pronouns = member.pronouns();
-                        let pronouns = pronouns.as_bytes();
-                        buf[..pronouns.len()].copy_from_slice(pronouns);
-                        Some(InResponse::Accepted(&buf[..pronouns.len()]))
+                        Some(InResponse::Accepted(copy_truncated(buf, &pronouns)))
                     }
                 })
             }
-            Ok(sysusb::Request::GetState)
-                if req.length == core::mem::size_of::<CurrentMenu>() as u16 =>
-            {
+            Ok(sysusb::Request::GetMemberId) => {
+                debug!("Sending member id {}", req.value);
+
+                let offset = req.value as usize;
+                block_on(async {
+                    let badge = self.badge.lock().await;
+                    if badge.system.member_count() <= offset {
+                        trace!("Member {} not found", offset);
+                        Some(InResponse::Rejected)
+                    } else {
+                        let member = badge.system.member(offset);
+                        let id = member.id().unwrap_or_default();
+                        Some(InResponse::Accepted(copy_truncated(buf, &id)))
+                    }
+                })
+            }
+            Ok(sysusb::Request::GetMemberDisplayName) => {
+                debug!("Sending member display name {}", req.value);
+
+                let offset = req.value as usize;
+                block_on(async {
+                    let badge = self.badge.lock().await;
+                    if badge.system.member_count() <= offset {
+                        trace!("Member {} not found", offset);
+                        Some(InResponse::Rejected)
+                    } else {
+                        let member = badge.system.member(offset);
+                        let name = member.display_name().unwrap_or_default();
+                        Some(InResponse::Accepted(copy_truncated(buf, &name)))
+                    }
+                })
+            }
+            Ok(sysusb::Request::GetState) if req.length as usize >= STATE_MAX_LEN => {
                 debug!("Sending state");
-                defmt::assert!(buf.len() >= core::mem::size_of::<CurrentMenu>());
+                defmt::assert!(buf.len() >= STATE_MAX_LEN);
 
                 let len = block_on(async {
                     let badge = self.badge.lock().await;
-                    let state = badge.current().as_bytes();
-                    buf[..state.len()].copy_from_slice(state);
-                    state.len()
+                    badge.current().encode(buf)
                 });
 
-                Some(InResponse::Accepted(&buf[..len]))
+                Some(match len {
+                    Ok(len) => InResponse::Accepted(&buf[..len]),
+                    Err(_) => InResponse::Rejected,
+                })
             }
             Ok(sysusb::Request::GetVersion) => {
                 use sysusb::VersionType;
@@ -295,3 +326,13 @@ impl Handler for Control {
         }
     }
 }
+
+/// Copies as much of `s` into `buf` as fits, without splitting a character.
+fn copy_truncated<'a>(buf: &'a mut [u8], s: &str) -> &'a [u8] {
+    let mut len = s.len().min(buf.len());
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    buf[..len].copy_from_slice(&s.as_bytes()[..len]);
+    &buf[..len]
+}
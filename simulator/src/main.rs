@@ -3,12 +3,14 @@ mod defmt_stderr;
 use embedded_graphics::pixelcolor::BinaryColor;
 use embedded_graphics::prelude::*;
 
+use embedded_graphics::primitives::{PrimitiveStyleBuilder, StrokeAlignment};
 use embedded_graphics_simulator::{
     sdl2::Keycode, BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent,
     Window,
 };
+use sysbadge::badge::{Refresh, Sysbadge};
 use sysbadge::system::SystemVec;
-use sysbadge::{badge::Sysbadge, Button};
+use sysbadge::Button;
 
 fn main() -> Result<(), core::convert::Infallible> {
     let mut display =
@@ -21,6 +23,14 @@ fn main() -> Result<(), core::convert::Infallible> {
 
     let system = create_system();
     let mut sysbadge = Sysbadge::new(display, system);
+    if let Some(max) = std::env::var("SYSBADGE_MEMBER_CELLS")
+        .ok()
+        .and_then(|max| max.parse().ok())
+    {
+        sysbadge.max_member_cells = max;
+        // the front shown first has to fit as well
+        sysbadge.reset();
+    }
 
     sysbadge.draw().unwrap();
 
@@ -41,9 +51,16 @@ fn main() -> Result<(), core::convert::Infallible> {
 }
 
 fn run_loop(mut window: Window, mut sysbadge: Sysbadge<SimulatorDisplay<BinaryColor>, SystemVec>) {
+    let show_dirty = std::env::var_os("SYSBADGE_SHOW_DIRTY").is_some();
     'running: loop {
-        sysbadge.draw().expect("Failed to redraw screen");
-        window.update(&sysbadge.display);
+        if sysbadge.draw().expect("Failed to redraw screen") && show_dirty {
+            println!("refresh: {:?}", sysbadge.refresh());
+        }
+        if show_dirty {
+            window.update(&with_dirty_regions(&sysbadge));
+        } else {
+            window.update(&sysbadge.display);
+        }
 
         for event in window.events() {
             match event {
@@ -82,6 +99,24 @@ fn run_loop(mut window: Window, mut sysbadge: Sysbadge<SimulatorDisplay<BinaryCo
     }
 }
 
+/// Copy of the display with the regions of the last partial refresh outlined.
+fn with_dirty_regions(
+    sysbadge: &Sysbadge<SimulatorDisplay<BinaryColor>, SystemVec>,
+) -> SimulatorDisplay<BinaryColor> {
+    let mut display = sysbadge.display.clone();
+    if let Refresh::Partial(regions) = sysbadge.refresh() {
+        let style = PrimitiveStyleBuilder::new()
+            .stroke_color(BinaryColor::On)
+            .stroke_width(1)
+            .stroke_alignment(StrokeAlignment::Inside)
+            .build();
+        for region in regions.iter() {
+            region.into_styled(style).draw(&mut display).unwrap();
+        }
+    }
+    display
+}
+
 fn run_buttons(
     mut window: Window,
     mut sysbadge: Sysbadge<SimulatorDisplay<BinaryColor>, SystemVec>,
@@ -121,10 +156,23 @@ fn create_system() -> SystemVec {
     system.members.push(sysbadge::system::MemberStrings {
         name: "Myriad".to_string(),
         pronouns: "they/them".to_string(),
+        description: Some(
+            "Example member of the PluralKit example system, used to show the detail page."
+                .to_string(),
+        ),
+        birthday: sysbadge::system::Birthday::parse("0004-06-28"),
+        color: Some(0x93c47d),
+        ..Default::default()
     });
     system.members.push(sysbadge::system::MemberStrings {
         name: "Tester T. Testington".to_string(),
         pronouns: "".to_string(),
+        ..Default::default()
+    });
+    system.members.push(sysbadge::system::MemberStrings {
+        name: "Zoë Ренат Ελένη".to_string(),
+        pronouns: "she/они".to_string(),
+        ..Default::default()
     });
     system
 }
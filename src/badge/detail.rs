@@ -0,0 +1,202 @@
+use core::fmt::Write;
+
+use embedded_graphics::geometry::AnchorY;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::Alignment;
+
+use super::font::{self, FontSet, StrBuf, TextBox};
+use super::BINARY_COLOR_ON;
+use crate::system::Member;
+use crate::DrawResult;
+
+const MARGIN: u32 = 5;
+/// Height of the name and the info line above the body.
+const HEADER_HEIGHT: u32 = 40;
+static BODY_FONT: &FontSet = &font::FONT_6X10;
+
+/// Bounds of the badge display, used to paginate without a draw target.
+pub(super) fn display_bounds() -> Rectangle {
+    Rectangle::new(Point::zero(), Size::new(crate::WIDTH, crate::HEIGHT))
+}
+
+/// Number of pages needed to show the description and fields of `member`.
+pub(super) fn pages<M: Member>(member: &M, bounds: Rectangle) -> u8 {
+    let body = body_bounds(bounds);
+    let mut lines = 0;
+    for_each_line(member, BODY_FONT.chars_per_line(body.size.width), |_, _| {
+        lines += 1
+    });
+
+    let per_page = lines_per_page(body);
+    ((lines + per_page - 1) / per_page).clamp(1, u8::MAX as usize) as u8
+}
+
+/// Draws the name, pronouns, birthday and colour of `member` with a page of the longer text.
+pub(super) fn draw<M, D>(member: &M, page: u8, target: &mut D) -> DrawResult<D>
+where
+    M: Member,
+    D: DrawTarget,
+    <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+{
+    let bounds = target.bounding_box();
+    let inner = bounds.offset(-(MARGIN as i32));
+
+    let pages = pages(member, bounds);
+    let mut page_text = StrBuf::<8>::new();
+    if pages > 1 {
+        let _ = write!(page_text, "{}/{}", page.saturating_add(1), pages);
+    }
+    let page_width = font::FONT_6X10.text_width(page_text.as_str());
+
+    let name_bounds = Rectangle::new(
+        inner.top_left,
+        Size::new(inner.size.width - page_width - MARGIN, 22),
+    );
+    super::with_display_name(member, |name| {
+        TextBox::fit(name, name_bounds.size, &font::NAME_FONTS[1..]).draw(
+            name_bounds,
+            Alignment::Left,
+            AnchorY::Top,
+            BINARY_COLOR_ON,
+            target,
+        )
+    })?;
+    font::FONT_6X10.draw(
+        page_text.as_str(),
+        inner.anchor_point(embedded_graphics::geometry::AnchorPoint::TopRight)
+            + Point::new(0, font::FONT_6X10.baseline() as i32),
+        Alignment::Right,
+        BINARY_COLOR_ON,
+        target,
+    )?;
+
+    // pronouns, birthday and colour share one line below the name
+    let mut info = StrBuf::<128>::new();
+    let pronouns = member.pronouns();
+    let mut parts = [pronouns.as_ref(), "", ""];
+    let mut birthday = StrBuf::<16>::new();
+    if let Some(date) = member.birthday() {
+        let _ = write!(birthday, "{}", date);
+        parts[1] = birthday.as_str();
+    }
+    let mut color = StrBuf::<8>::new();
+    if let Some(rgb) = member.color() {
+        let _ = write!(color, "#{:06x}", rgb & 0xff_ff_ff);
+        parts[2] = color.as_str();
+    }
+    for part in parts.iter().filter(|part| !part.is_empty()) {
+        if !info.as_str().is_empty() {
+            let _ = info.write_str(" · ");
+        }
+        let _ = info.write_str(part);
+    }
+    let info_bounds = Rectangle::new(
+        inner.top_left + Point::new(0, 24),
+        Size::new(inner.size.width, 13),
+    );
+    TextBox::fit(info.as_str(), info_bounds.size, &font::TEXT_FONTS[1..]).draw(
+        info_bounds,
+        Alignment::Left,
+        AnchorY::Top,
+        BINARY_COLOR_ON,
+        target,
+    )?;
+
+    target.fill_solid(
+        &Rectangle::new(
+            bounds.top_left + Point::new(MARGIN as i32, HEADER_HEIGHT as i32 - 2),
+            Size::new(inner.size.width, 1),
+        ),
+        BINARY_COLOR_ON.into(),
+    )?;
+
+    let body = body_bounds(bounds);
+    let height = BODY_FONT.character_size().height;
+    let first = page as usize * lines_per_page(body);
+    let last = first + lines_per_page(body);
+    let mut index = 0;
+    let mut result = Ok(());
+    for_each_line(
+        member,
+        BODY_FONT.chars_per_line(body.size.width),
+        |line, heading| {
+            if (first..last).contains(&index) && result.is_ok() {
+                let position = body.top_left
+                    + Point::new(
+                        0,
+                        ((index - first) as u32 * height + BODY_FONT.baseline()) as i32,
+                    );
+                result = BODY_FONT
+                    .draw(line, position, Alignment::Left, BINARY_COLOR_ON, target)
+                    .and_then(|end| {
+                        if heading {
+                            BODY_FONT.draw(":", end, Alignment::Left, BINARY_COLOR_ON, target)?;
+                        }
+                        Ok(())
+                    });
+            }
+            index += 1;
+        },
+    );
+    result?;
+
+    if index == 0 {
+        BODY_FONT.draw(
+            "No further details",
+            body.top_left + Point::new(0, BODY_FONT.baseline() as i32),
+            Alignment::Left,
+            BINARY_COLOR_ON,
+            target,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn body_bounds(bounds: Rectangle) -> Rectangle {
+    Rectangle::new(
+        bounds.top_left + Point::new(MARGIN as i32, HEADER_HEIGHT as i32),
+        Size::new(
+            bounds.size.width - MARGIN * 2,
+            bounds.size.height - HEADER_HEIGHT - MARGIN,
+        ),
+    )
+}
+
+fn lines_per_page(body: Rectangle) -> usize {
+    (body.size.height / BODY_FONT.character_size().height).max(1) as usize
+}
+
+/// Wraps the description and the fields of `member` into lines of at most `chars` characters.
+///
+/// The last line of a field name is passed with `true` and drawn with a trailing colon.
+fn for_each_line<M: Member>(member: &M, chars: usize, mut f: impl FnMut(&str, bool)) {
+    if chars < 2 {
+        return;
+    }
+
+    let mut paragraph = |text: &str, heading: bool| {
+        // leave room for the colon after field names
+        let chars = if heading { chars - 1 } else { chars };
+        let mut rest = text.trim();
+        while !rest.is_empty() {
+            let (line, tail) = font::split_line(rest, chars);
+            f(line, heading && tail.is_empty());
+            rest = tail;
+        }
+    };
+
+    if let Some(description) = member.description() {
+        for line in description.lines() {
+            paragraph(line, false);
+        }
+    }
+    for index in 0..member.field_count() {
+        if let Some((name, value)) = member.field(index) {
+            paragraph(name, true);
+            paragraph(value, false);
+        }
+    }
+}
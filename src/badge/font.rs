@@ -0,0 +1,446 @@
+//! Unicode aware text rendering.
+//!
+//! The mono fonts shipped with embedded-graphics only cover a single ISO/IEC 8859 part each.
+//! A [`FontSet`] groups equally sized fonts for several of those parts, so names mixing Latin,
+//! Cyrillic and Greek characters can be drawn in one line. Characters none of the fonts contain
+//! are drawn as a replacement box.
+//!
+//! [`TextBox`] lays text out to fit into a given size, stepping down through a list of font sets
+//! and wrapping onto a second line where there is room.
+
+use crate::DrawResult;
+use embedded_graphics::geometry::AnchorY;
+use embedded_graphics::mono_font::mapping::{self, StrGlyphMapping};
+use embedded_graphics::mono_font::{
+    iso_8859_1, iso_8859_13, iso_8859_15, iso_8859_16, iso_8859_2, iso_8859_3, iso_8859_4,
+    iso_8859_5, iso_8859_7, iso_8859_9, MonoFont, MonoTextStyle,
+};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle, StyledDrawable};
+use embedded_graphics::text::{Alignment, Baseline, Text};
+
+/// A mono font together with the mapping describing which characters it contains.
+#[derive(Clone, Copy)]
+pub(crate) struct Glyphs {
+    font: &'static MonoFont<'static>,
+    mapping: &'static StrGlyphMapping<'static>,
+}
+
+impl Glyphs {
+    const fn new(
+        font: &'static MonoFont<'static>,
+        mapping: &'static StrGlyphMapping<'static>,
+    ) -> Self {
+        Self { font, mapping }
+    }
+}
+
+/// Equally sized fonts, searched in order for the first one containing a character.
+pub(crate) struct FontSet {
+    glyphs: &'static [Glyphs],
+}
+
+macro_rules! unicode_font_set {
+    ($font:ident) => {
+        FontSet::new(&[
+            Glyphs::new(&iso_8859_1::$font, &mapping::ISO_8859_1),
+            Glyphs::new(&iso_8859_15::$font, &mapping::ISO_8859_15),
+            Glyphs::new(&iso_8859_2::$font, &mapping::ISO_8859_2),
+            Glyphs::new(&iso_8859_3::$font, &mapping::ISO_8859_3),
+            Glyphs::new(&iso_8859_4::$font, &mapping::ISO_8859_4),
+            Glyphs::new(&iso_8859_9::$font, &mapping::ISO_8859_9),
+            Glyphs::new(&iso_8859_13::$font, &mapping::ISO_8859_13),
+            Glyphs::new(&iso_8859_16::$font, &mapping::ISO_8859_16),
+            Glyphs::new(&iso_8859_5::$font, &mapping::ISO_8859_5),
+            Glyphs::new(&iso_8859_7::$font, &mapping::ISO_8859_7),
+        ])
+    };
+}
+
+/// ProFont only ships ASCII glyphs, use [`FONT_10X20`] for anything else.
+pub(crate) static PROFONT_24_POINT: FontSet =
+    FontSet::new(&[Glyphs::new(&profont::PROFONT_24_POINT, &mapping::ASCII)]);
+pub(crate) static FONT_10X20: FontSet = unicode_font_set!(FONT_10X20);
+pub(crate) static FONT_8X13: FontSet = unicode_font_set!(FONT_8X13);
+pub(crate) static FONT_6X10: FontSet = unicode_font_set!(FONT_6X10);
+/// Compact font for the small cells of grid layouts.
+pub(crate) static FONT_5X8: FontSet = unicode_font_set!(FONT_5X8);
+
+/// Font sets for member names, from the largest to the smallest.
+pub(crate) static NAME_FONTS: &[&FontSet] = &[
+    &PROFONT_24_POINT,
+    &FONT_10X20,
+    &FONT_8X13,
+    &FONT_6X10,
+    &FONT_5X8,
+];
+/// Font sets for secondary text like pronouns, from the largest to the smallest.
+pub(crate) static TEXT_FONTS: &[&FontSet] = &[&FONT_10X20, &FONT_8X13, &FONT_6X10, &FONT_5X8];
+
+/// None of the ISO 8859 parts contain an ellipsis, it is drawn by hand.
+const ELLIPSIS: char = '…';
+
+impl FontSet {
+    const fn new(glyphs: &'static [Glyphs]) -> Self {
+        Self { glyphs }
+    }
+
+    /// Size of a single character cell, shared by all fonts of the set.
+    pub(crate) fn character_size(&self) -> Size {
+        self.glyphs[0].font.character_size
+    }
+
+    /// Offset from the top of the character cell to the baseline.
+    pub(crate) fn baseline(&self) -> u32 {
+        self.glyphs[0].font.baseline
+    }
+
+    /// Horizontal distance from one character to the next.
+    fn advance(&self) -> u32 {
+        self.character_size().width + self.glyphs[0].font.character_spacing
+    }
+
+    /// Number of characters fitting into a line of `width` pixels.
+    pub(crate) fn chars_per_line(&self, width: u32) -> usize {
+        ((width + self.glyphs[0].font.character_spacing) / self.advance()) as usize
+    }
+
+    /// Returns true if every character of `text` can be drawn without a replacement box.
+    pub(crate) fn covers(&self, text: &str) -> bool {
+        text.chars().all(|c| self.font_for(c).is_some())
+    }
+
+    /// Width in pixels `text` occupies when drawn with this set.
+    pub(crate) fn text_width(&self, text: &str) -> u32 {
+        let chars = text.chars().count() as u32;
+        (chars * self.advance()).saturating_sub(self.glyphs[0].font.character_spacing)
+    }
+
+    fn font_for(&self, c: char) -> Option<&'static MonoFont<'static>> {
+        self.glyphs
+            .iter()
+            .find(|glyphs| glyphs.mapping.contains(c))
+            .map(|glyphs| glyphs.font)
+    }
+
+    /// Draws `text` with its alphabetic baseline at `position`.
+    ///
+    /// Returns the position after the last drawn character, like [`Text::draw`].
+    pub(crate) fn draw<D>(
+        &self,
+        text: &str,
+        position: Point,
+        alignment: Alignment,
+        color: BinaryColor,
+        target: &mut D,
+    ) -> DrawResult<D, Point>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    {
+        let mut position = match alignment {
+            Alignment::Left => position,
+            Alignment::Center => position - Point::new(self.text_width(text) as i32 / 2, 0),
+            Alignment::Right => position - Point::new(self.text_width(text) as i32, 0),
+        };
+
+        for c in text.chars() {
+            position = self.draw_char(c, position, color, target)?;
+        }
+
+        Ok(position)
+    }
+
+    fn draw_char<D>(
+        &self,
+        c: char,
+        position: Point,
+        color: BinaryColor,
+        target: &mut D,
+    ) -> DrawResult<D, Point>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    {
+        match self.font_for(c) {
+            Some(font) => {
+                let mut buf = [0; 4];
+                Text::with_baseline(
+                    c.encode_utf8(&mut buf),
+                    position,
+                    MonoTextStyle::new(font, color.into()),
+                    Baseline::Alphabetic,
+                )
+                .draw(target)
+            }
+            None if c == ELLIPSIS => {
+                self.draw_ellipsis(position, color, target)?;
+                Ok(position + Point::new(self.advance() as i32, 0))
+            }
+            None => {
+                self.replacement_box(position)
+                    .draw_styled(&PrimitiveStyle::with_stroke(color.into(), 1), target)?;
+                Ok(position + Point::new(self.advance() as i32, 0))
+            }
+        }
+    }
+
+    /// Three square dots sitting on the baseline.
+    fn draw_ellipsis<D>(&self, position: Point, color: BinaryColor, target: &mut D) -> DrawResult<D>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    {
+        let width = self.character_size().width;
+        let dot = core::cmp::max(1, width / 5);
+        let step = width / 3;
+
+        for i in 0..3 {
+            Rectangle::new(
+                position + Point::new((i * step + (step - dot) / 2) as i32, 1 - dot as i32),
+                Size::new(dot, dot),
+            )
+            .draw_styled(&PrimitiveStyle::with_fill(color.into()), target)?;
+        }
+
+        Ok(())
+    }
+
+    /// Box filling the area between the top of the character cell and the baseline.
+    fn replacement_box(&self, position: Point) -> Rectangle {
+        let size = self.character_size();
+        let baseline = self.glyphs[0].font.baseline;
+
+        Rectangle::new(
+            position + Point::new(1, 1 - baseline as i32),
+            Size::new(size.width.saturating_sub(2), baseline.saturating_sub(1)),
+        )
+    }
+}
+
+/// Text laid out to fit into a box, see [`TextBox::fit`].
+pub(crate) struct TextBox<'a> {
+    font: &'static FontSet,
+    lines: [&'a str; 2],
+    line_count: usize,
+    ellipsis: bool,
+}
+
+impl<'a> TextBox<'a> {
+    /// Lays out `text` with the largest of `fonts` it fits into `size` with.
+    ///
+    /// The text wraps onto a second line if there is room for it. Font sets not covering all
+    /// characters of the text are skipped, unless none of them does. If the text does not fit
+    /// even with the smallest font, it is cut off and ends with an ellipsis.
+    ///
+    /// `fonts` has to be ordered from the largest to the smallest font set.
+    pub(crate) fn fit(text: &'a str, size: Size, fonts: &[&'static FontSet]) -> Self {
+        debug_assert!(!fonts.is_empty());
+
+        let text = text.trim();
+        let coverable = fonts.iter().any(|font| font.covers(text));
+        let mut smallest = fonts[fonts.len() - 1];
+
+        for &font in fonts {
+            let line_height = font.character_size().height;
+            if line_height > size.height {
+                continue;
+            }
+            smallest = font;
+            if coverable && !font.covers(text) {
+                continue;
+            }
+
+            let max_lines = core::cmp::min(2, size.height / line_height) as usize;
+            if let Some(text_box) = Self::wrap(text, font, size.width, max_lines, false) {
+                return text_box;
+            }
+        }
+
+        let max_lines = core::cmp::min(2, size.height / smallest.character_size().height);
+        Self::wrap(text, smallest, size.width, max_lines.max(1) as usize, true)
+            .unwrap_or_else(|| Self::empty(smallest))
+    }
+
+    fn empty(font: &'static FontSet) -> Self {
+        Self {
+            font,
+            lines: [""; 2],
+            line_count: 1,
+            ellipsis: false,
+        }
+    }
+
+    /// Breaks `text` into at most `max_lines` lines, returns `None` if it does not fit and
+    /// `truncate` is not set.
+    fn wrap(
+        text: &'a str,
+        font: &'static FontSet,
+        width: u32,
+        max_lines: usize,
+        truncate: bool,
+    ) -> Option<Self> {
+        let chars_per_line = font.chars_per_line(width).max(1);
+        let mut text_box = Self::empty(font);
+        let mut rest = text;
+
+        for line in 0..max_lines {
+            if rest.chars().count() <= chars_per_line {
+                text_box.lines[line] = rest;
+                text_box.line_count = line + 1;
+                return Some(text_box);
+            }
+            if line + 1 == max_lines {
+                break;
+            }
+
+            let (head, tail) = split_line(rest, chars_per_line);
+            text_box.lines[line] = head;
+            rest = tail;
+        }
+
+        if !truncate {
+            return None;
+        }
+
+        text_box.line_count = max_lines;
+        text_box.lines[max_lines - 1] = truncate_chars(rest, chars_per_line - 1).trim_end();
+        text_box.ellipsis = true;
+        Some(text_box)
+    }
+
+    /// Size the laid out text occupies.
+    pub(crate) fn size(&self) -> Size {
+        let width = (0..self.line_count)
+            .map(|line| self.line_width(line))
+            .max()
+            .unwrap_or(0);
+
+        Size::new(
+            width,
+            self.line_count as u32 * self.font.character_size().height,
+        )
+    }
+
+    fn line_width(&self, line: usize) -> u32 {
+        let text = self.lines[line];
+        if self.ellipsis && line + 1 == self.line_count {
+            self.font.text_width(text) + self.font.advance()
+        } else {
+            self.font.text_width(text)
+        }
+    }
+
+    /// Draws the text inside of `bounds`, horizontally aligned by `alignment` and vertically
+    /// by `anchor`.
+    pub(crate) fn draw<D>(
+        &self,
+        bounds: Rectangle,
+        alignment: Alignment,
+        anchor: AnchorY,
+        color: BinaryColor,
+        target: &mut D,
+    ) -> DrawResult<D>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    {
+        let line_height = self.font.character_size().height as i32;
+        let height = self.size().height as i32;
+        let top = match anchor {
+            AnchorY::Top => bounds.top_left.y,
+            AnchorY::Center => bounds.top_left.y + (bounds.size.height as i32 - height) / 2,
+            AnchorY::Bottom => bounds.top_left.y + bounds.size.height as i32 - height,
+        };
+
+        for line in 0..self.line_count {
+            let width = self.line_width(line) as i32;
+            let x = match alignment {
+                Alignment::Left => bounds.top_left.x,
+                Alignment::Center => bounds.top_left.x + (bounds.size.width as i32 - width) / 2,
+                Alignment::Right => bounds.top_left.x + bounds.size.width as i32 - width,
+            };
+            let y = top + line as i32 * line_height + self.font.baseline() as i32;
+
+            let position = self.font.draw(
+                self.lines[line],
+                Point::new(x, y),
+                Alignment::Left,
+                color,
+                target,
+            )?;
+            if self.ellipsis && line + 1 == self.line_count {
+                self.font.draw_char(ELLIPSIS, position, color, target)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits `text` into a line of at most `chars` characters and the remaining text.
+///
+/// Lines are broken after the last space or slash, or in the middle of a word if it does not
+/// contain either.
+pub(crate) fn split_line(text: &str, chars: usize) -> (&str, &str) {
+    let end = match text.char_indices().nth(chars) {
+        Some((idx, _)) => idx,
+        None => return (text, ""),
+    };
+
+    let mut split = end;
+    for (idx, c) in text[..end].char_indices().skip(1) {
+        if c.is_whitespace() {
+            split = idx;
+        } else if c == '/' {
+            split = idx + c.len_utf8();
+        }
+    }
+    if text[end..].starts_with(char::is_whitespace) {
+        split = end;
+    }
+
+    (text[..split].trim_end(), text[split..].trim_start())
+}
+
+/// Cuts `str` after at most `max` characters, never splitting a UTF-8 sequence.
+fn truncate_chars(str: &str, max: usize) -> &str {
+    match str.char_indices().nth(max) {
+        Some((idx, _)) => &str[..idx],
+        None => str,
+    }
+}
+
+/// Fixed size buffer to format text into without allocating.
+pub(crate) struct StrBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> StrBuf<N> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        // only whole `str`s are copied in `write_str`
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> core::fmt::Write for StrBuf<N> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > N {
+            return Err(core::fmt::Error);
+        }
+
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
@@ -0,0 +1,1425 @@
+mod detail;
+mod font;
+mod qr;
+mod refresh;
+
+use self::font::TextBox;
+pub use self::refresh::{DirtyRegions, Refresh, DEFAULT_FULL_REFRESH_INTERVAL, MAX_DIRTY_REGIONS};
+
+use crate::system::{Bitmap, Member, SystemError};
+use crate::{Button, DrawResult, System};
+use core::fmt::Write;
+use embedded_graphics::geometry::{AnchorPoint, AnchorX, AnchorY};
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{
+    Circle, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, StyledDrawable,
+};
+use embedded_graphics::text::{Alignment, Text};
+
+#[cfg(not(feature = "invert"))]
+const BINARY_COLOR_OFF: BinaryColor = BinaryColor::Off;
+
+#[cfg(feature = "invert")]
+const BINARY_COLOR_OFF: BinaryColor = BinaryColor::On;
+
+#[cfg(not(feature = "invert"))]
+const BINARY_COLOR_ON: BinaryColor = BinaryColor::On;
+
+#[cfg(feature = "invert")]
+const BINARY_COLOR_ON: BinaryColor = BinaryColor::Off;
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Default)]
+#[repr(u8)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Select {
+    #[default]
+    None,
+    Select,
+    Edit,
+    /// Letter picker opened from [`Select::Edit`], see [`CurrentMembers::search`].
+    Search,
+    /// Group picker opened from [`Select::Search`], see [`CurrentMembers::search`].
+    Group,
+}
+
+impl Select {
+    pub(crate) fn stroke_with(&self) -> u32 {
+        match self {
+            Self::None => 1,
+            Self::Select => 2,
+            Self::Edit | Self::Search | Self::Group => 4,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MemberCell {
+    pub id: u16,
+}
+
+/// Number of member cells [`CurrentMembers`] has room for.
+pub const MAX_MEMBER_CELLS: usize = 16;
+/// Default for the configurable maximum of member cells, see [`Sysbadge::max_member_cells`].
+pub const DEFAULT_MEMBER_CELLS: u8 = 8;
+/// Number of cells fitting on the screen at once, further cells are shown on the next page.
+pub const CELLS_PER_PAGE: u8 = 8;
+
+/// Width of the strip on the right holding the page indicator.
+const PAGE_INDICATOR_WIDTH: u32 = 8;
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CurrentMembers {
+    pub members: [MemberCell; MAX_MEMBER_CELLS],
+    pub sel: (u8, Select),
+    pub len: u8,
+    /// Number of cells that can be added, at most [`MAX_MEMBER_CELLS`].
+    pub max: u8,
+    /// First member of the letter group chosen in [`Select::Search`], or the group chosen in
+    /// [`Select::Group`] counting from 1, with 0 for all members.
+    pub search: u16,
+    /// Group the members are stepped through in [`Select::Edit`], `None` for all members.
+    pub group: Option<u16>,
+}
+
+impl core::default::Default for CurrentMembers {
+    fn default() -> Self {
+        Self {
+            len: 1,
+            sel: (0, Select::Select),
+            members: core::default::Default::default(),
+            max: DEFAULT_MEMBER_CELLS,
+            search: 0,
+            group: None,
+        }
+    }
+}
+
+impl CurrentMembers {
+    /// Cells showing the members fronting when `system` was downloaded, `None` without a front.
+    ///
+    /// Shows at most `max_member_cells` members, see [`Sysbadge::max_member_cells`].
+    pub fn front<S: System>(system: &S, max_member_cells: u8) -> Option<Self> {
+        let max = core::cmp::min(max_member_cells as usize, MAX_MEMBER_CELLS);
+        let len = core::cmp::min(system.front_len(), max);
+        if len == 0 {
+            return None;
+        }
+
+        let mut members = Self {
+            len: len as u8,
+            sel: (0, Select::None),
+            max: max as u8,
+            ..Default::default()
+        };
+        for (index, cell) in members.members[..len].iter_mut().enumerate() {
+            cell.id = system.front_member(index).unwrap_or_default();
+        }
+        Some(members)
+    }
+
+    fn sel_for_cell(&self, idx: u8) -> Select {
+        if self.sel.0 == idx {
+            self.sel.1
+        } else {
+            Select::None
+        }
+    }
+
+    /// Page holding the selected cell.
+    pub fn page(&self) -> u8 {
+        core::cmp::min(self.sel.0, self.len.saturating_sub(1)) / CELLS_PER_PAGE
+    }
+
+    pub fn pages(&self) -> u8 {
+        (self.len + CELLS_PER_PAGE - 1) / CELLS_PER_PAGE
+    }
+
+    fn button_press<S: System>(&mut self, button: Button, system: &S) {
+        let members = system.member_count();
+        match button {
+            Button::Up | Button::Down if self.sel.1 == Select::Search => {
+                if button == Button::Up {
+                    self.search = prev_initial(system, self.search);
+                } else {
+                    self.search = next_initial(system, self.search);
+                }
+            }
+            Button::B if self.sel.1 == Select::Search => {
+                self.members[self.sel.0 as usize].id = self.search;
+                self.sel.1 = Select::Edit;
+            }
+            Button::A if self.sel.1 == Select::Search && system.group_count() != 0 => {
+                self.search = self.group.map_or(0, |group| group + 1);
+                self.sel.1 = Select::Group;
+            }
+            Button::A | Button::C if self.sel.1 == Select::Search => {
+                self.sel.1 = Select::Edit;
+            }
+            Button::Up | Button::Down if self.sel.1 == Select::Group => {
+                let groups = system.group_count() as u16;
+                if button == Button::Up {
+                    self.search = dec_wrapping(self.search, groups);
+                } else {
+                    self.search = inc_wrapping(self.search, groups);
+                }
+            }
+            Button::B if self.sel.1 == Select::Group => {
+                self.group = self.search.checked_sub(1);
+                if let Some(group) = self.group {
+                    if let Some(first) = system.group_member(group as usize, 0) {
+                        self.members[self.sel.0 as usize].id = first;
+                    }
+                }
+                self.sel.1 = Select::Edit;
+            }
+            Button::A | Button::C if self.sel.1 == Select::Group => {
+                self.sel.1 = Select::Edit;
+            }
+            Button::A if self.sel.1 == Select::Edit && members != 0 => {
+                self.search = initial_start(system, self.members[self.sel.0 as usize].id);
+                self.sel.1 = Select::Search;
+            }
+            Button::Up | Button::Down if self.sel.1 == Select::None => {
+                self.sel.1 = Select::Select;
+                if button == Button::Up {
+                    self.sel.0 = 0;
+                } else {
+                    self.sel.0 = self.len - 1;
+                }
+            }
+            Button::Up | Button::Down if self.sel.1 == Select::Select => {
+                if button == Button::Up {
+                    self.sel.0 = dec_wrapping(self.sel.0, self.len - 1);
+                } else {
+                    self.sel.0 = inc_wrapping(self.sel.0, self.len - 1);
+                }
+            }
+            Button::Up | Button::Down
+                if self.sel.1 == Select::Edit
+                    && self
+                        .group
+                        .is_some_and(|group| system.group_len(group as usize) != 0) =>
+            {
+                let cell = &mut self.members[self.sel.0 as usize];
+                cell.id = step_in_group(
+                    system,
+                    self.group.unwrap_or_default() as usize,
+                    cell.id,
+                    button == Button::Up,
+                );
+            }
+            Button::Up | Button::Down if self.sel.1 == Select::Edit => {
+                if button == Button::Up {
+                    self.members[self.sel.0 as usize].id =
+                        inc_wrapping(self.members[self.sel.0 as usize].id, members as u16 - 1);
+                } else {
+                    self.members[self.sel.0 as usize].id =
+                        dec_wrapping(self.members[self.sel.0 as usize].id, members as u16 - 1);
+                }
+            }
+            Button::C if self.sel.1 == Select::Edit => {
+                self.len -= 1;
+                if self.sel.0 != self.len {
+                    self.members[self.sel.0 as usize] = self.members[self.len as usize];
+                }
+                self.sel.0 = core::cmp::min(self.sel.0, self.len - 1);
+                self.sel.1 = Select::None;
+            }
+            Button::C => {
+                self.sel.1 = Select::None;
+                if self.len < core::cmp::min(self.max, MAX_MEMBER_CELLS as u8) {
+                    self.len += 1;
+                }
+            }
+            Button::B => {
+                if self.sel.1 == Select::Select {
+                    self.sel.1 = Select::Edit;
+                } else {
+                    self.sel.1 = Select::Select;
+                }
+            }
+            _ => {
+                #[cfg(feature = "defmt")]
+                defmt::warn!("Unhandled member button press: {:?}", button)
+            }
+        }
+    }
+
+    fn draw<D, S>(&self, system: &S, target: &mut D) -> DrawResult<D>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+        S: System,
+    {
+        debug_assert!(self.len != 0 && self.len as usize <= MAX_MEMBER_CELLS);
+
+        let area = self.cells_area(target.bounding_box());
+        if self.pages() > 1 {
+            self.draw_page_indicator(target)?;
+        }
+
+        let (first, count) = self.visible_cells();
+        for i in 0..count {
+            DrawableMember::new(
+                system.member(self.members[(first + i) as usize].id as usize),
+                Self::cell_bounds(i, count, area),
+                self.sel_for_cell(first + i),
+            )
+            .draw(target)?;
+        }
+
+        match self.sel.1 {
+            Select::Search => self.draw_search(system, target)?,
+            Select::Group => self.draw_group(system, target)?,
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Box in the center showing the chosen initial and the member it jumps to.
+    fn draw_search<D, S>(&self, system: &S, target: &mut D) -> DrawResult<D>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+        S: System,
+    {
+        let bounds = Self::search_bounds(target.bounding_box());
+        bounds.draw_styled(
+            &PrimitiveStyleBuilder::new()
+                .fill_color(BINARY_COLOR_OFF.into())
+                .stroke_color(BINARY_COLOR_ON.into())
+                .stroke_width(2)
+                .build(),
+            target,
+        )?;
+
+        let member = system.member(self.search as usize);
+        let mut buf = [0; 4];
+        let letter = with_display_name(&member, initial).encode_utf8(&mut buf);
+        let inner = bounds.offset(-5);
+        let letter = TextBox::fit(letter, inner.size, font::NAME_FONTS);
+        let size = letter.size();
+        letter.draw(
+            inner,
+            Alignment::Center,
+            AnchorY::Top,
+            BINARY_COLOR_ON,
+            target,
+        )?;
+
+        let rest = inner.resized_height(inner.size.height - size.height - 2, AnchorY::Bottom);
+        with_display_name(&member, |name| {
+            TextBox::fit(name, rest.size, &font::TEXT_FONTS[1..]).draw(
+                rest,
+                Alignment::Center,
+                AnchorY::Bottom,
+                BINARY_COLOR_ON,
+                target,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Box in the center showing the chosen group and its size.
+    fn draw_group<D, S>(&self, system: &S, target: &mut D) -> DrawResult<D>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+        S: System,
+    {
+        let bounds = Self::search_bounds(target.bounding_box());
+        bounds.draw_styled(
+            &PrimitiveStyleBuilder::new()
+                .fill_color(BINARY_COLOR_OFF.into())
+                .stroke_color(BINARY_COLOR_ON.into())
+                .stroke_width(2)
+                .build(),
+            target,
+        )?;
+
+        let group = self.search.checked_sub(1).map(usize::from);
+        let name = group.and_then(|group| system.group_name(group));
+        let name = name.as_ref().map_or("All members", |name| name.as_ref());
+        let mut count = font::StrBuf::<24>::new();
+        let _ = match group {
+            Some(group) => write!(count, "{} members", system.group_len(group)),
+            None => write!(count, "{} members", system.member_count()),
+        };
+
+        let inner = bounds.offset(-5);
+        let name = TextBox::fit(name, inner.size, &font::NAME_FONTS[1..]);
+        let size = name.size();
+        name.draw(
+            inner,
+            Alignment::Center,
+            AnchorY::Top,
+            BINARY_COLOR_ON,
+            target,
+        )?;
+
+        let rest = inner.resized_height(inner.size.height - size.height - 2, AnchorY::Bottom);
+        TextBox::fit(count.as_str(), rest.size, &font::TEXT_FONTS[1..]).draw(
+            rest,
+            Alignment::Center,
+            AnchorY::Bottom,
+            BINARY_COLOR_ON,
+            target,
+        )?;
+
+        Ok(())
+    }
+
+    /// Index of the first cell on the current page and the number of cells shown.
+    fn visible_cells(&self) -> (u8, u8) {
+        let first = self.page() * CELLS_PER_PAGE;
+        (first, core::cmp::min(self.len - first, CELLS_PER_PAGE))
+    }
+
+    /// Part of the display used by cells, leaving room for the page indicator.
+    fn cells_area(&self, bounds: Rectangle) -> Rectangle {
+        if self.pages() > 1 {
+            bounds.resized_width(bounds.size.width - PAGE_INDICATOR_WIDTH, AnchorX::Left)
+        } else {
+            bounds
+        }
+    }
+
+    fn search_bounds(bounds: Rectangle) -> Rectangle {
+        Rectangle::with_center(bounds.center(), Size::new(160, 72))
+    }
+
+    /// Regions that differ between `prev` and `self`, or `None` if the layout changed.
+    fn dirty_regions(&self, prev: &Self, bounds: Rectangle) -> Option<DirtyRegions> {
+        if self.len != prev.len || self.page() != prev.page() {
+            return None;
+        }
+
+        let mut regions = DirtyRegions::new();
+        let area = self.cells_area(bounds);
+        let (first, count) = self.visible_cells();
+        for i in 0..count {
+            let idx = first + i;
+            if self.group != prev.group
+                || self.members[idx as usize] != prev.members[idx as usize]
+                || self.sel_for_cell(idx) != prev.sel_for_cell(idx)
+            {
+                regions.add(Self::cell_bounds(i, count, area));
+            }
+        }
+
+        let searching = |sel: Select| matches!(sel, Select::Search | Select::Group);
+        if (searching(self.sel.1) || searching(prev.sel.1))
+            && (self.sel.1 != prev.sel.1 || self.search != prev.search)
+        {
+            regions.add(Self::search_bounds(bounds));
+        }
+
+        Some(regions)
+    }
+
+    /// Payload of [`CurrentMenu::encode`], `buf` has to hold [`CurrentMembers::encoded_len`].
+    fn encode(&self, buf: &mut [u8]) {
+        buf[0] = self.len;
+        buf[1] = self.max;
+        buf[2] = self.sel.0;
+        buf[3] = self.sel.1 as u8;
+        buf[4..6].copy_from_slice(&self.search.to_le_bytes());
+        buf[6..8].copy_from_slice(&self.group.unwrap_or(NO_GROUP).to_le_bytes());
+        for (i, cell) in self.members[..self.len as usize].iter().enumerate() {
+            buf[8 + i * 2..10 + i * 2].copy_from_slice(&cell.id.to_le_bytes());
+        }
+    }
+
+    fn decode(buf: &[u8]) -> Result<Self, StateError> {
+        let [len, max, sel, select, s0, s1, g0, g1, ids @ ..] = buf else {
+            return Err(StateError::Truncated);
+        };
+
+        let (len, max, sel) = (*len, *max, *sel);
+        if len == 0 || len as usize > MAX_MEMBER_CELLS || max as usize > MAX_MEMBER_CELLS {
+            return Err(StateError::InvalidLength(len));
+        }
+        if sel >= len {
+            return Err(StateError::InvalidSelection(sel));
+        }
+        if ids.len() != len as usize * 2 {
+            return Err(StateError::Truncated);
+        }
+
+        let mut members = CurrentMembers {
+            sel: (sel, Select::try_from(*select)?),
+            len,
+            max,
+            search: u16::from_le_bytes([*s0, *s1]),
+            group: Some(u16::from_le_bytes([*g0, *g1])).filter(|&group| group != NO_GROUP),
+            ..Default::default()
+        };
+        for (cell, id) in members.members.iter_mut().zip(ids.chunks_exact(2)) {
+            cell.id = u16::from_le_bytes([id[0], id[1]]);
+        }
+
+        Ok(members)
+    }
+
+    fn encoded_len(&self) -> usize {
+        8 + self.len as usize * 2
+    }
+
+    /// Checks the member ids, the group and the letter picker against `system`, a decoded state
+    /// can come from a host that has another system.
+    fn validate<S: System>(&self, system: &S) -> Result<(), StateError> {
+        // the cells of a system without members still show member 0
+        let members = core::cmp::max(system.member_count(), 1);
+        if let Some(cell) = self.members[..self.len as usize]
+            .iter()
+            .find(|cell| cell.id as usize >= members)
+        {
+            return Err(StateError::InvalidMember(cell.id));
+        }
+
+        if let Some(group) = self
+            .group
+            .filter(|&group| group as usize >= system.group_count())
+        {
+            return Err(StateError::InvalidGroup(group));
+        }
+
+        let search = match self.sel.1 {
+            Select::Search => (self.search as usize) < members,
+            Select::Group => self.search as usize <= system.group_count(),
+            _ => true,
+        };
+        if !search {
+            return Err(StateError::InvalidSearch(self.search));
+        }
+
+        Ok(())
+    }
+
+    /// Id of the member in the selected cell.
+    pub fn selected(&self) -> u16 {
+        self.members[self.sel.0 as usize].id
+    }
+
+    /// Bounds of the `index`th of `count` cells shown on a page covering `area`.
+    ///
+    /// Up to four cells are stacked in rows, more cells are laid out in a grid of two columns
+    /// with three or four rows.
+    fn cell_bounds(index: u8, count: u8, area: Rectangle) -> Rectangle {
+        let (columns, rows) = match count {
+            0..=4 => (1, count.max(1) as u32),
+            5 | 6 => (2, 3),
+            _ => (2, 4),
+        };
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        let size = Size::new(area.size.width / columns, area.size.height / rows);
+
+        Rectangle::new(
+            area.top_left + Point::new((column * size.width) as i32, (row * size.height) as i32),
+            size,
+        )
+    }
+
+    /// Column of dots on the right edge, the current page is filled.
+    fn draw_page_indicator<D>(&self, target: &mut D) -> DrawResult<D>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    {
+        const DIAMETER: u32 = 5;
+        const SPACING: u32 = 8;
+
+        let bounds = target.bounding_box();
+        let pages = self.pages() as u32;
+        let x = (bounds.size.width - PAGE_INDICATOR_WIDTH + (PAGE_INDICATOR_WIDTH - DIAMETER) / 2)
+            as i32;
+        let top = (bounds.size.height - pages * SPACING) as i32 / 2;
+
+        for page in 0..pages {
+            let style = if page == self.page() as u32 {
+                PrimitiveStyle::with_fill(BINARY_COLOR_ON.into())
+            } else {
+                PrimitiveStyle::with_stroke(BINARY_COLOR_ON.into(), 1)
+            };
+            Circle::new(
+                bounds.top_left + Point::new(x, top + (page * SPACING) as i32),
+                DIAMETER,
+            )
+            .draw_styled(&style, target)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+#[repr(u8)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CurrentMenu {
+    SystemName,
+    Version,
+    Member(CurrentMembers),
+    InvalidSystem,
+    /// QR code linking to the profile of the system.
+    Qr,
+    /// Details of the selected member, opened from [`CurrentMenu::Member`].
+    MemberDetail {
+        members: CurrentMembers,
+        page: u8,
+    },
+    /// List of the stored systems, opened from [`CurrentMenu::SystemName`].
+    SystemSwitcher {
+        sel: u8,
+    },
+}
+
+impl CurrentMenu {
+    pub fn change<S: System>(&mut self, button: Button, system: &S) {
+        self.change_with(button, system, MAX_MEMBER_CELLS as u8)
+    }
+
+    /// Like [`CurrentMenu::change`], showing at most `max_member_cells` fronting members.
+    fn change_with<S: System>(&mut self, button: Button, system: &S, max_member_cells: u8) {
+        match self {
+            Self::SystemName if button == Button::B => *self = Self::Version,
+            Self::SystemName if button == Button::A => *self = Self::Qr,
+            Self::Qr if button == Button::A || button == Button::B => *self = Self::SystemName,
+            Self::Version if button == Button::B => *self = Self::SystemName,
+            Self::SystemName if button == Button::C => {
+                *self = Self::Member(CurrentMembers::default())
+            }
+            Self::SystemName | Self::InvalidSystem
+                if (button == Button::Up || button == Button::Down)
+                    && system.system_count() > 1 =>
+            {
+                *self = Self::SystemSwitcher {
+                    sel: system.selected_system() as u8,
+                }
+            }
+            Self::SystemSwitcher { sel } => {
+                let max = (system.system_count().max(1) - 1) as u8;
+                match button {
+                    Button::Up => *sel = dec_wrapping(core::cmp::min(*sel, max), max),
+                    Button::Down => *sel = inc_wrapping(core::cmp::min(*sel, max), max),
+                    Button::B => {
+                        system.select_system(*sel as usize);
+                        *self = Self::home(system, max_member_cells);
+                    }
+                    _ => *self = Self::home(system, max_member_cells),
+                }
+            }
+            Self::Member(ref c) if button == Button::C && c.len == 1 && c.sel.1 == Select::Edit => {
+                *self = Self::SystemName
+            }
+            Self::Member(c) if button == Button::A && c.sel.1 == Select::Select => {
+                *self = Self::MemberDetail {
+                    members: c.clone(),
+                    page: 0,
+                }
+            }
+            Self::Member(c) => c.button_press(button, system),
+            Self::MemberDetail { members, page } => match button {
+                Button::Up => *page = page.saturating_sub(1),
+                Button::Down => {
+                    let member = system.member(members.selected() as usize);
+                    if page.saturating_add(1) < detail::pages(&member, detail::display_bounds()) {
+                        *page += 1;
+                    }
+                }
+                _ => *self = Self::Member(members.clone()),
+            },
+            Self::InvalidSystem => (),
+            _ => {
+                #[cfg(feature = "defmt")]
+                defmt::warn!("Unhandled button press: {:?}", button)
+            }
+        }
+    }
+
+    /// First menu shown for `system`, its front if it was stored with one.
+    fn home<S: System>(system: &S, max_member_cells: u8) -> Self {
+        if !system.is_valid() {
+            Self::InvalidSystem
+        } else if let Some(members) = CurrentMembers::front(system, max_member_cells) {
+            Self::Member(members)
+        } else {
+            Self::SystemName
+        }
+    }
+
+    /// Regions of the display that differ between `prev` and `self`.
+    ///
+    /// Returns `None` if the whole display changed.
+    pub fn dirty_regions(&self, prev: &Self, bounds: Rectangle) -> Option<DirtyRegions> {
+        match (self, prev) {
+            (Self::Member(cur), Self::Member(prev)) => cur.dirty_regions(prev, bounds),
+            (cur, prev) if cur == prev => Some(DirtyRegions::new()),
+            _ => None,
+        }
+    }
+
+    /// Writes the wire encoding of the state into `buf`, returning the number of bytes used.
+    ///
+    /// The encoding starts with [`STATE_VERSION`] and a tag for the menu, followed by the menu
+    /// specific payload. Multi-byte values are little endian.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, StateError> {
+        let len = self.encoded_len();
+        if buf.len() < len {
+            return Err(StateError::BufferTooSmall);
+        }
+
+        buf[0] = STATE_VERSION;
+        buf[1] = self.tag();
+        match self {
+            Self::Member(members) => members.encode(&mut buf[2..]),
+            Self::MemberDetail { members, page } => {
+                buf[2] = *page;
+                members.encode(&mut buf[3..]);
+            }
+            Self::SystemSwitcher { sel } => buf[2] = *sel,
+            _ => (),
+        }
+
+        Ok(len)
+    }
+
+    /// Parses a state written by [`CurrentMenu::encode`], rejecting anything malformed.
+    pub fn decode(buf: &[u8]) -> Result<Self, StateError> {
+        match buf {
+            [] | [_] => Err(StateError::Truncated),
+            [version, ..] if *version != STATE_VERSION => Err(StateError::Version(*version)),
+            [_, 0, rest @ ..] if rest.is_empty() => Ok(Self::SystemName),
+            [_, 1, rest @ ..] if rest.is_empty() => Ok(Self::Version),
+            [_, 3, rest @ ..] if rest.is_empty() => Ok(Self::InvalidSystem),
+            [_, 4, rest @ ..] if rest.is_empty() => Ok(Self::Qr),
+            [_, 2, rest @ ..] => Ok(Self::Member(CurrentMembers::decode(rest)?)),
+            [_, 5, page, rest @ ..] => Ok(Self::MemberDetail {
+                members: CurrentMembers::decode(rest)?,
+                page: *page,
+            }),
+            [_, 6, sel] => Ok(Self::SystemSwitcher { sel: *sel }),
+            [_, 0..=6, ..] => Err(StateError::Truncated),
+            [_, tag, ..] => Err(StateError::InvalidTag(*tag)),
+        }
+    }
+
+    /// Number of bytes [`CurrentMenu::encode`] writes for this state.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Self::Member(members) => 2 + members.encoded_len(),
+            Self::MemberDetail { members, .. } => 3 + members.encoded_len(),
+            Self::SystemSwitcher { .. } => 3,
+            _ => 2,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            Self::SystemName => 0,
+            Self::Version => 1,
+            Self::Member(_) => 2,
+            Self::InvalidSystem => 3,
+            Self::Qr => 4,
+            Self::MemberDetail { .. } => 5,
+            Self::SystemSwitcher { .. } => 6,
+        }
+    }
+}
+
+/// Version of the [`CurrentMenu`] wire encoding, bumped whenever a payload layout changes.
+pub const STATE_VERSION: u8 = 4;
+/// Upper bound of [`CurrentMenu::encoded_len`].
+pub const STATE_MAX_LEN: usize = 11 + MAX_MEMBER_CELLS * 2;
+
+/// Encoding of [`CurrentMembers::group`] being `None`.
+const NO_GROUP: u16 = u16::MAX;
+
+/// Errors decoding a [`CurrentMenu`] from its wire encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StateError {
+    /// Data ended early or has trailing bytes.
+    Truncated,
+    /// Encoding version is not [`STATE_VERSION`].
+    Version(u8),
+    InvalidTag(u8),
+    InvalidLength(u8),
+    InvalidSelection(u8),
+    InvalidSelect(u8),
+    /// Member id not in the shown system.
+    InvalidMember(u16),
+    /// Letter picker or group choice not in the shown system.
+    InvalidSearch(u16),
+    /// Group not in the shown system.
+    InvalidGroup(u16),
+    /// Output buffer cannot hold the encoded state.
+    BufferTooSmall,
+}
+
+impl core::fmt::Display for StateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "state has the wrong length"),
+            Self::Version(v) => write!(f, "unsupported state version {}", v),
+            Self::InvalidTag(t) => write!(f, "invalid menu tag {}", t),
+            Self::InvalidLength(l) => write!(f, "invalid member count {}", l),
+            Self::InvalidSelection(s) => write!(f, "selected cell {} out of range", s),
+            Self::InvalidSelect(s) => write!(f, "invalid selection mode {}", s),
+            Self::InvalidMember(id) => write!(f, "member {} out of range", id),
+            Self::InvalidSearch(s) => write!(f, "search position {} out of range", s),
+            Self::InvalidGroup(g) => write!(f, "group {} out of range", g),
+            Self::BufferTooSmall => write!(f, "buffer too small for state"),
+        }
+    }
+}
+
+impl core::error::Error for StateError {}
+
+impl TryFrom<u8> for Select {
+    type Error = StateError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            x if x == Select::None as u8 => Ok(Select::None),
+            x if x == Select::Select as u8 => Ok(Select::Select),
+            x if x == Select::Edit as u8 => Ok(Select::Edit),
+            x if x == Select::Search as u8 => Ok(Select::Search),
+            x if x == Select::Group as u8 => Ok(Select::Group),
+            _ => Err(StateError::InvalidSelect(value)),
+        }
+    }
+}
+
+pub struct Sysbadge<D, S>
+where
+    D: DrawTarget,
+    <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    S: System,
+{
+    pub display: D,
+    pub system: S,
+    pub serial: Option<&'static str>,
+    /// Maximum number of member cells that can be added or shown for the front, at most
+    /// [`MAX_MEMBER_CELLS`].
+    pub max_member_cells: u8,
+    /// Number of partial refreshes before [`Refresh::Full`] is requested again.
+    pub full_refresh_interval: u8,
+    current: CurrentMenu,
+    /// Menu shown by the last draw, used to find the dirty regions.
+    drawn: Option<CurrentMenu>,
+    refresh: Refresh,
+    partial_refreshes: u8,
+    hash: u16,
+}
+
+impl<D, S> Sysbadge<D, S>
+where
+    D: DrawTarget,
+    <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    S: System,
+{
+    pub fn new(display: D, system: S) -> Self {
+        let current = CurrentMenu::home(&system, DEFAULT_MEMBER_CELLS);
+
+        Self {
+            display,
+            system,
+            serial: None,
+            max_member_cells: DEFAULT_MEMBER_CELLS,
+            full_refresh_interval: DEFAULT_FULL_REFRESH_INTERVAL,
+            current,
+            drawn: None,
+            refresh: Refresh::Full,
+            partial_refreshes: 0,
+            hash: 0,
+        }
+    }
+
+    pub fn press(&mut self, button: Button) {
+        let selected = self.system.selected_system();
+        self.current
+            .change_with(button, &self.system, self.max_member_cells);
+        if self.system.selected_system() != selected {
+            // the same menu shows another system now
+            self.drawn = None;
+        }
+        if let CurrentMenu::Member(members) = &mut self.current {
+            members.max = core::cmp::min(self.max_member_cells, MAX_MEMBER_CELLS as u8);
+        }
+
+        #[cfg(feature = "defmt")]
+        defmt::debug!(
+            "Pressed button: {:?}, switched to: {:?}",
+            button,
+            self.current
+        );
+    }
+
+    /// Redraws the display if the menu changed, see [`Sysbadge::refresh`] for the changed area.
+    pub fn draw(&mut self) -> DrawResult<D, bool> {
+        let hash = self.hash();
+        if self.hash == hash {
+            return Ok(false);
+        }
+
+        let regions = self.drawn.as_ref().and_then(|prev| {
+            self.current
+                .dirty_regions(prev, self.display.bounding_box())
+        });
+
+        self.redraw()?;
+        self.hash = hash;
+        self.refresh = match regions {
+            Some(regions) if self.partial_refreshes < self.full_refresh_interval => {
+                self.partial_refreshes += 1;
+                Refresh::Partial(regions)
+            }
+            _ => {
+                self.partial_refreshes = 0;
+                Refresh::Full
+            }
+        };
+        Ok(true)
+    }
+
+    pub fn force_draw(&mut self) -> DrawResult<D> {
+        self.redraw()?;
+        self.partial_refreshes = 0;
+        self.refresh = Refresh::Full;
+        Ok(())
+    }
+
+    fn redraw(&mut self) -> DrawResult<D> {
+        self.display.clear(BINARY_COLOR_OFF.into())?;
+        match self.current {
+            CurrentMenu::InvalidSystem => self.draw_invalid_system(),
+            CurrentMenu::SystemName => self.draw_system_name(),
+            CurrentMenu::Version => self.draw_version(),
+            CurrentMenu::Member(ref cur) => cur.draw(&self.system, &mut self.display),
+            CurrentMenu::Qr => self.draw_qr(),
+            CurrentMenu::MemberDetail { ref members, page } => detail::draw(
+                &self.system.member(members.selected() as usize),
+                page,
+                &mut self.display,
+            ),
+            CurrentMenu::SystemSwitcher { sel } => self.draw_system_switcher(sel),
+        }?;
+        self.drawn = Some(self.current.clone());
+        Ok(())
+    }
+
+    /// Panel update needed to show the result of the last draw.
+    ///
+    /// Drivers without partial update support can always do a full refresh.
+    pub fn refresh(&self) -> Refresh {
+        self.refresh
+    }
+
+    pub fn current(&self) -> &CurrentMenu {
+        &self.current
+    }
+
+    /// Shows `state`, rejecting member ids the system does not have.
+    ///
+    /// A detail page past the end of the member details is moved to the last page.
+    pub fn set_current(&mut self, mut state: CurrentMenu) -> Result<(), StateError> {
+        match &mut state {
+            CurrentMenu::Member(members) => members.validate(&self.system)?,
+            CurrentMenu::MemberDetail { members, page } => {
+                members.validate(&self.system)?;
+                let member = self.system.member(members.selected() as usize);
+                let pages = detail::pages(&member, detail::display_bounds());
+                *page = core::cmp::min(*page, pages - 1);
+            }
+            _ => (),
+        }
+
+        self.current = state;
+        Ok(())
+    }
+
+    fn hash(&self) -> u16 {
+        let mut buf = [0; STATE_MAX_LEN];
+        let len = self.current.encode(&mut buf).unwrap_or(0);
+
+        let mut crc: crc16::State<crc16::BUYPASS> = crc16::State::new();
+        crc.update(&buf[..len]);
+        crc.update(&[self.system.selected_system() as u8]);
+        crc.get()
+    }
+
+    fn draw_system_name(&mut self) -> DrawResult<D> {
+        font::FONT_10X20.draw(
+            self.system.name().as_ref(),
+            self.display.bounding_box().center(),
+            Alignment::Center,
+            BINARY_COLOR_ON,
+            &mut self.display,
+        )?;
+
+        Ok(())
+    }
+
+    /// One row per system, the selected row is framed and the shown system marked with a dot.
+    fn draw_system_switcher(&mut self, sel: u8) -> DrawResult<D> {
+        const DOT_DIAMETER: u32 = 7;
+
+        let bounds = self.display.bounding_box();
+        let count = self.system.system_count().max(1);
+        let height = bounds.size.height / count as u32;
+        for index in 0..count {
+            let row = Rectangle::new(
+                bounds.top_left + Point::new(0, (index as u32 * height) as i32),
+                Size::new(bounds.size.width, height),
+            );
+            if index == sel as usize {
+                row.offset(-1).draw_styled(
+                    &PrimitiveStyle::with_stroke(BINARY_COLOR_ON.into(), 2),
+                    &mut self.display,
+                )?;
+            }
+            if index == self.system.selected_system() {
+                Circle::with_center(
+                    row.anchor_point(AnchorPoint::CenterLeft) + Point::new(10, 0),
+                    DOT_DIAMETER,
+                )
+                .draw_styled(
+                    &PrimitiveStyle::with_fill(BINARY_COLOR_ON.into()),
+                    &mut self.display,
+                )?;
+            }
+
+            let name = self.system.system_name(index);
+            let name = name.as_ref().map_or("Invalid system", |name| name.as_ref());
+            let text = row
+                .offset(-4)
+                .resized_width(row.size.width - 8 - DOT_DIAMETER - 12, AnchorX::Right);
+            TextBox::fit(name, text.size, &font::NAME_FONTS[1..]).draw(
+                text,
+                Alignment::Left,
+                AnchorY::Center,
+                BINARY_COLOR_ON,
+                &mut self.display,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_qr(&mut self) -> DrawResult<D> {
+        match self.system.profile_url() {
+            Some(url) => qr::draw(url, self.system.name().as_ref(), &mut self.display),
+            None => qr::draw_message("No profile link", &mut self.display),
+        }
+    }
+
+    fn draw_version(&mut self) -> DrawResult<D> {
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::ascii::FONT_10X20,
+            BINARY_COLOR_ON.into(),
+        );
+
+        Text::with_alignment(
+            "Sysbadge",
+            self.display.bounding_box().center().x_axis() + Point::new(0, 20),
+            text_style,
+            Alignment::Center,
+        )
+        .draw(&mut self.display)?;
+
+        self.draw_version_and_serial(Point::new(5, 60))?;
+
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::ascii::FONT_9X18,
+            BINARY_COLOR_ON.into(),
+        );
+
+        Text::with_alignment(
+            concat!(
+                "matrix: ",
+                env!("SYSBADGE_MATRIX", "missing matrix configuration")
+            ),
+            Point::new(5, 105),
+            text_style,
+            Alignment::Left,
+        )
+        .draw(&mut self.display)?;
+        Text::with_alignment(
+            concat!("web: ", env!("SYSBADGE_WEB", "missing web configuration")),
+            Point::new(5, 120),
+            text_style,
+            Alignment::Left,
+        )
+        .draw(&mut self.display)?;
+
+        Ok(())
+    }
+
+    fn draw_version_and_serial(&mut self, start: Point) -> DrawResult<D> {
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::ascii::FONT_10X20,
+            BINARY_COLOR_ON.into(),
+        );
+
+        let point = Text::with_alignment("Version: ", start, text_style, Alignment::Left)
+            .draw(&mut self.display)?;
+        Text::with_alignment(
+            env!("CARGO_PKG_VERSION"),
+            point,
+            text_style,
+            Alignment::Left,
+        )
+        .draw(&mut self.display)?;
+
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::ascii::FONT_9X18,
+            BINARY_COLOR_ON.into(),
+        );
+
+        if let Some(serial) = &self.serial {
+            let point = Text::with_alignment(
+                "Serial: ",
+                start + Point::new(0, 30),
+                text_style,
+                Alignment::Left,
+            )
+            .draw(&mut self.display)?;
+            Text::with_alignment(serial, point, text_style, Alignment::Left)
+                .draw(&mut self.display)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_invalid_system(&mut self) -> DrawResult<D> {
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::ascii::FONT_10X20,
+            BINARY_COLOR_ON.into(),
+        );
+
+        let error = self.system.error();
+        let mut detail = font::StrBuf::<64>::new();
+        let title = match error {
+            Some(SystemError::Blank) => {
+                let _ = detail.write_str("Flash a system to show it here");
+                "No System Data"
+            }
+            Some(SystemError::UnsupportedVersion(version)) => {
+                let _ = write!(
+                    detail,
+                    "Format {}, firmware reads {}",
+                    version,
+                    crate::system::FORMAT_VERSION
+                );
+                "Unsupported System"
+            }
+            Some(err @ (SystemError::Checksum | SystemError::Truncated(_))) => {
+                let _ = write!(detail, "{}", err);
+                "System Data Corrupted"
+            }
+            Some(err) => {
+                let _ = write!(detail, "{}", err);
+                "System Data Invalid"
+            }
+            None => "System Data Invalid",
+        };
+
+        let center = self.display.bounding_box().center().x_axis();
+        Text::with_alignment(
+            title,
+            center + Point::new(0, 30),
+            text_style,
+            Alignment::Center,
+        )
+        .draw(&mut self.display)?;
+        font::FONT_6X10.draw(
+            detail.as_str(),
+            center + Point::new(0, 50),
+            Alignment::Center,
+            BINARY_COLOR_ON,
+            &mut self.display,
+        )?;
+
+        self.draw_version_and_serial(Point::new(5, 75))?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "simulator")]
+    pub fn reset(&mut self) {
+        self.current = CurrentMenu::home(&self.system, self.max_member_cells);
+    }
+}
+
+/// Calls `f` with the name shown for `member`, its display name if set.
+fn with_display_name<M: Member, R>(member: &M, f: impl FnOnce(&str) -> R) -> R {
+    let name = member.name();
+    let display_name = member.display_name();
+    f(display_name
+        .as_ref()
+        .map_or(name.as_ref(), |display_name| display_name.as_ref()))
+}
+
+/// Uppercased first character of a name, used to group members in the letter picker.
+fn initial(name: &str) -> char {
+    name.chars()
+        .next()
+        .map(|c| c.to_uppercase().next().unwrap_or(c))
+        .unwrap_or(' ')
+}
+
+fn member_initial<S: System>(system: &S, id: u16) -> char {
+    with_display_name(&system.member(id as usize), initial)
+}
+
+/// Initials of all members with their ids.
+///
+/// Members can be in any order, a merged system keeps its own order, so the letter picker looks
+/// at all of them instead of relying on members being sorted by name.
+fn initials<S: System>(system: &S) -> impl Iterator<Item = (char, u16)> + '_ {
+    (0..system.member_count() as u16).map(move |id| (member_initial(system, id), id))
+}
+
+/// First member sharing the initial of `id`.
+fn initial_start<S: System>(system: &S, id: u16) -> u16 {
+    let letter = member_initial(system, id);
+    initials(system)
+        .find(|&(initial, _)| initial == letter)
+        .map_or(id, |(_, id)| id)
+}
+
+/// First member of the next initial, wrapping around to the first initial.
+fn next_initial<S: System>(system: &S, id: u16) -> u16 {
+    let letter = member_initial(system, id);
+    initials(system)
+        .filter(|&(initial, _)| initial > letter)
+        .min()
+        .or_else(|| initials(system).min())
+        .map_or(0, |(_, id)| id)
+}
+
+/// First member of the previous initial, wrapping around to the last initial.
+fn prev_initial<S: System>(system: &S, id: u16) -> u16 {
+    let letter = member_initial(system, id);
+    let key = |&(initial, id): &(char, u16)| (initial, core::cmp::Reverse(id));
+    initials(system)
+        .filter(|&(initial, _)| initial < letter)
+        .max_by_key(key)
+        .or_else(|| initials(system).max_by_key(key))
+        .map_or(0, |(_, id)| id)
+}
+
+/// Member after or before `id` in `group`, starting at the first member if `id` is not in it.
+fn step_in_group<S: System>(system: &S, group: usize, id: u16, forward: bool) -> u16 {
+    let len = system.group_len(group);
+    let pos = (0..len).find(|&idx| system.group_member(group, idx) == Some(id));
+    let next = match pos {
+        Some(pos) if forward => inc_wrapping(pos, len - 1),
+        Some(pos) => dec_wrapping(pos, len - 1),
+        None => 0,
+    };
+
+    system.group_member(group, next).unwrap_or(id)
+}
+
+fn inc_wrapping<T>(cur: T, max: T) -> T
+where
+    T: core::ops::Add<T, Output = T>,
+    T: Eq,
+    T: TryFrom<u8>,
+{
+    if cur == max {
+        unsafe { T::try_from(0).unwrap_unchecked() }
+    } else {
+        cur + unsafe { T::try_from(1).unwrap_unchecked() }
+    }
+}
+
+fn dec_wrapping<T>(cur: T, max: T) -> T
+where
+    T: core::ops::Sub<T, Output = T>,
+    T: Eq,
+    T: TryFrom<u8>,
+{
+    if cur == unsafe { T::try_from(0).unwrap_unchecked() } {
+        max
+    } else {
+        cur - unsafe { T::try_from(1).unwrap_unchecked() }
+    }
+}
+
+fn draw_bitmap<D>(bitmap: &Bitmap, top_left: Point, target: &mut D) -> DrawResult<D>
+where
+    D: DrawTarget,
+    <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+{
+    target.draw_iter((0..bitmap.height).flat_map(|y| {
+        (0..bitmap.width).map(move |x| {
+            let color = if bitmap.pixel(x, y) {
+                BINARY_COLOR_ON
+            } else {
+                BINARY_COLOR_OFF
+            };
+            Pixel(top_left + Point::new(x as i32, y as i32), color.into())
+        })
+    }))
+}
+
+pub(crate) struct DrawableMember<C, M>
+where
+    C: PixelColor + From<BinaryColor>,
+    M: Member,
+{
+    member: M,
+    bounds: Rectangle,
+    select: Select,
+    _color: core::marker::PhantomData<C>,
+}
+
+impl<C, M> DrawableMember<C, M>
+where
+    C: PixelColor + From<BinaryColor>,
+    M: Member,
+{
+    pub fn new(member: M, bounds: Rectangle, select: Select) -> Self {
+        Self {
+            member,
+            bounds,
+            select,
+            _color: core::marker::PhantomData,
+        }
+    }
+
+    /// Space for the text of the cell, leaving room for the selection border.
+    fn inner(&self) -> Rectangle {
+        Rectangle::new(
+            self.bounds.top_left + Point::new(5, 5),
+            self.bounds.size.saturating_sub(Size::new(10, 10)),
+        )
+    }
+
+    /// Draws the pronouns into `bounds`, returns the size they occupy.
+    ///
+    /// Tall cells show the pronouns on a line of their own at the bottom, smaller cells put
+    /// them right aligned next to the name.
+    fn pronoun<D>(&self, bounds: Rectangle, target: &mut D) -> DrawResult<D, Size>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    {
+        let pronouns = self.member.pronouns();
+        let (fonts, max_width, align, anchor) = match self.bounds.size.height {
+            x if x > 100 => (
+                &font::TEXT_FONTS[0..],
+                bounds.size.width,
+                Alignment::Left,
+                AnchorY::Bottom,
+            ),
+            x if x > 50 => (
+                &font::TEXT_FONTS[1..],
+                bounds.size.width,
+                Alignment::Left,
+                AnchorY::Bottom,
+            ),
+            x if x > 40 => (
+                &font::TEXT_FONTS[1..],
+                bounds.size.width * 2 / 5,
+                Alignment::Right,
+                AnchorY::Top,
+            ),
+            _ => (
+                &font::TEXT_FONTS[2..],
+                bounds.size.width * 2 / 5,
+                Alignment::Right,
+                AnchorY::Top,
+            ),
+        };
+
+        let text = TextBox::fit(
+            pronouns.as_ref(),
+            Size::new(max_width, fonts[0].character_size().height),
+            fonts,
+        );
+        // FIXME: brackets around text
+        text.draw(bounds, align, anchor, BINARY_COLOR_ON, target)?;
+
+        Ok(text.size())
+    }
+
+    fn name<D>(&self, bounds: Rectangle, target: &mut D) -> DrawResult<D>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    {
+        with_display_name(&self.member, |name| {
+            TextBox::fit(name, bounds.size, font::NAME_FONTS).draw(
+                bounds,
+                Alignment::Left,
+                AnchorY::Top,
+                BINARY_COLOR_ON,
+                target,
+            )
+        })
+    }
+}
+
+impl<C, M> Drawable for DrawableMember<C, M>
+where
+    C: PixelColor + From<BinaryColor>,
+    M: Member,
+{
+    type Color = C;
+    type Output = Rectangle;
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget,
+        <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+    {
+        let bound_style =
+            PrimitiveStyle::with_stroke(BINARY_COLOR_ON.into(), self.select.stroke_with());
+        self.bounds.draw_styled(&bound_style, target)?;
+
+        let mut inner = self.inner();
+        if let Some(avatar) = self.member.avatar() {
+            if inner.size.height >= avatar.height as u32 {
+                let top_left = inner.top_left
+                    + Point::new(0, (inner.size.height - avatar.height as u32) as i32 / 2);
+                draw_bitmap(&avatar, top_left, target)?;
+                inner = inner.resized_width(
+                    inner.size.width.saturating_sub(avatar.width as u32 + 5),
+                    AnchorX::Right,
+                );
+            }
+        }
+
+        let pronouns = if self.member.pronouns().as_ref().is_empty() {
+            Size::zero()
+        } else {
+            self.pronoun(inner, target)?
+        };
+
+        let name_bounds = if self.bounds.size.height > 50 {
+            inner.resized_height(
+                inner.size.height.saturating_sub(pronouns.height + 2),
+                AnchorY::Top,
+            )
+        } else if pronouns.width > 0 {
+            inner.resized_width(
+                inner.size.width.saturating_sub(pronouns.width + 5),
+                AnchorX::Left,
+            )
+        } else {
+            inner
+        };
+        self.name(name_bounds, target)?;
+
+        Ok(self.bounds)
+    }
+}
@@ -0,0 +1,113 @@
+use core::fmt::Write;
+
+use embedded_graphics::geometry::AnchorY;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::Alignment;
+use qrcodegen_no_heap::{QrCode, QrCodeEcc, Version};
+
+use super::font::{self, StrBuf, TextBox};
+use super::BINARY_COLOR_ON;
+use crate::system::ProfileUrl;
+use crate::DrawResult;
+
+/// Largest QR code version, still drawn with 2px modules on the 128px high panel.
+const MAX_VERSION: Version = Version::new(7);
+const BUFFER_LEN: usize = MAX_VERSION.buffer_len();
+/// Longest url fitting into [`MAX_VERSION`] with low error correction.
+const MAX_URL_LEN: usize = 154;
+/// Modules of white space around the code.
+const QUIET_ZONE: i32 = 2;
+
+/// Draws a QR code for `url` on the left and the system name and url on the right.
+pub(super) fn draw<D>(url: ProfileUrl, name: &str, target: &mut D) -> DrawResult<D>
+where
+    D: DrawTarget,
+    <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+{
+    let mut text = StrBuf::<MAX_URL_LEN>::new();
+    if write!(text, "{}", url).is_err() {
+        return draw_message("Profile link too long", target);
+    }
+
+    let mut tmp = [0; BUFFER_LEN];
+    let mut out = [0; BUFFER_LEN];
+    let Ok(qr) = QrCode::encode_text(
+        text.as_str(),
+        &mut tmp,
+        &mut out,
+        QrCodeEcc::Low,
+        Version::MIN,
+        MAX_VERSION,
+        None,
+        true,
+    ) else {
+        return draw_message("Profile link too long", target);
+    };
+
+    let bounds = target.bounding_box();
+    let modules = qr.size() + QUIET_ZONE * 2;
+    let scale = bounds.size.height as i32 / modules;
+    let offset = (bounds.size.height as i32 - qr.size() * scale) / 2;
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.get_module(x, y) {
+                target.fill_solid(
+                    &Rectangle::new(
+                        bounds.top_left + Point::new(offset + x * scale, offset + y * scale),
+                        Size::new(scale as u32, scale as u32),
+                    ),
+                    BINARY_COLOR_ON.into(),
+                )?;
+            }
+        }
+    }
+
+    let left = (offset * 2 + qr.size() * scale) as u32;
+    let text_bounds = Rectangle::new(
+        bounds.top_left + Point::new(left as i32, offset),
+        Size::new(
+            bounds.size.width - left - 5,
+            bounds.size.height - offset as u32 * 2,
+        ),
+    );
+    TextBox::fit(name, text_bounds.size, &font::NAME_FONTS[1..]).draw(
+        text_bounds,
+        Alignment::Left,
+        AnchorY::Top,
+        BINARY_COLOR_ON,
+        target,
+    )?;
+    TextBox::fit(
+        text.as_str(),
+        Size::new(text_bounds.size.width, text_bounds.size.height / 2),
+        &font::TEXT_FONTS[2..],
+    )
+    .draw(
+        text_bounds,
+        Alignment::Left,
+        AnchorY::Bottom,
+        BINARY_COLOR_ON,
+        target,
+    )?;
+
+    Ok(())
+}
+
+/// Shown instead of the code if the system has no profile or it cannot be encoded.
+pub(super) fn draw_message<D>(message: &str, target: &mut D) -> DrawResult<D>
+where
+    D: DrawTarget,
+    <D as DrawTarget>::Color: From<BinaryColor> + PixelColor,
+{
+    font::FONT_10X20.draw(
+        message,
+        target.bounding_box().center(),
+        Alignment::Center,
+        BINARY_COLOR_ON,
+        target,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+
+/// Number of rectangles tracked before further regions are merged into the last one.
+pub const MAX_DIRTY_REGIONS: usize = 4;
+
+/// Default number of partial refreshes after which a full refresh clears the ghosting.
+pub const DEFAULT_FULL_REFRESH_INTERVAL: u8 = 8;
+
+/// Panel update required after the last [`Sysbadge::draw`](super::Sysbadge::draw).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Refresh {
+    /// The whole display has to be refreshed.
+    Full,
+    /// Only the contained regions changed.
+    Partial(DirtyRegions),
+}
+
+/// Fixed capacity list of changed rectangles of the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DirtyRegions {
+    regions: [Rectangle; MAX_DIRTY_REGIONS],
+    len: u8,
+}
+
+impl DirtyRegions {
+    pub const fn new() -> Self {
+        Self {
+            regions: [Rectangle::zero(); MAX_DIRTY_REGIONS],
+            len: 0,
+        }
+    }
+
+    /// Adds a region, merging it into the last one if there is no space left.
+    pub fn add(&mut self, region: Rectangle) {
+        if region.is_zero_sized() {
+            return;
+        }
+
+        if (self.len as usize) < MAX_DIRTY_REGIONS {
+            self.regions[self.len as usize] = region;
+            self.len += 1;
+        } else {
+            let last = &mut self.regions[MAX_DIRTY_REGIONS - 1];
+            *last = envelope(*last, region);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rectangle> {
+        self.regions[..self.len as usize].iter()
+    }
+}
+
+impl Default for DirtyRegions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Smallest rectangle containing both `a` and `b`.
+fn envelope(a: Rectangle, b: Rectangle) -> Rectangle {
+    let (a_end, b_end) = (
+        a.top_left + a.size - Point::new(1, 1),
+        b.top_left + b.size - Point::new(1, 1),
+    );
+
+    Rectangle::with_corners(
+        a.top_left.component_min(b.top_left),
+        a_end.component_max(b_end),
+    )
+}
@@ -1,7 +1,8 @@
 #![no_std]
 #![feature(ptr_metadata)]
 #![feature(return_position_impl_trait_in_trait)]
-#![cfg_attr(feature = "downloaders", feature(async_fn_in_trait, error_in_core))]
+#![feature(error_in_core)]
+#![cfg_attr(feature = "downloaders", feature(async_fn_in_trait))]
 #![deny(unsafe_op_in_unsafe_fn)]
 
 #[cfg(feature = "alloc")]
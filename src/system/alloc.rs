@@ -1,13 +1,20 @@
-use crate::system::Member;
+use crate::system::{Birthday, Bitmap, Member, ProfileUrl};
 use crate::System;
 
 use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::{Encoding, Header, SystemError, SystemReader, DATA_REGION_SIZE, UNPACK_BUFFER_SIZE};
+#[cfg(feature = "uf2")]
+pub use uf2::Uf2Error;
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SourceId {
     PluralKit(String),
     Pronouns(String),
+    SimplyPlural(String),
+    Tupperbox(String),
 }
 
 /// Owned system utilizing a vec to hold members.
@@ -18,8 +25,41 @@ pub struct SystemVec {
     pub name: String,
     /// Optional source id
     pub source_id: Option<SourceId>,
+    /// Optional link shown as QR code instead of the profile of the source
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub url: Option<String>,
     /// Vector of members
     pub members: alloc::vec::Vec<MemberStrings>,
+    /// Member groups, referring to members by index
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "alloc::vec::Vec::is_empty")
+    )]
+    pub groups: alloc::vec::Vec<GroupVec>,
+    /// Members fronting when the system was downloaded, by index, the badge starts on them
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "alloc::vec::Vec::is_empty")
+    )]
+    pub front: alloc::vec::Vec<u16>,
+}
+
+/// Owned group of members.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, Default)]
+pub struct GroupVec {
+    pub name: String,
+    /// Stable id of the group in its source
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub id: Option<String>,
+    /// Indices into the members of the system
+    pub members: alloc::vec::Vec<u16>,
 }
 
 impl SystemVec {
@@ -27,27 +67,61 @@ impl SystemVec {
         Self {
             name,
             source_id: None,
+            url: None,
             members: alloc::vec::Vec::new(),
+            groups: alloc::vec::Vec::new(),
+            front: alloc::vec::Vec::new(),
         }
     }
 
     #[cfg(feature = "downloader-pk")]
     #[inline]
-    pub async fn fetch_pk(id: impl AsRef<str>) -> Result<Self, reqwest::Error> {
+    pub async fn fetch_pk(id: impl AsRef<str>) -> Result<Self, super::downloaders::DownloadError> {
         super::downloaders::PkDownloader::new().get(id).await
     }
 
+    /// Sorts the members by their shown name, keeping the groups and the front pointing at the
+    /// same members.
     pub fn sort_members(&mut self) {
-        self.members
-            .sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        let mut order: alloc::vec::Vec<usize> = (0..self.members.len()).collect();
+        order.sort_by_cached_key(|&idx| self.members[idx].shown_name().to_lowercase());
+
+        let mut new_index = alloc::vec![0u16; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new as u16;
+        }
+
+        let mut members: alloc::vec::Vec<Option<MemberStrings>> =
+            core::mem::take(&mut self.members)
+                .into_iter()
+                .map(Some)
+                .collect();
+        self.members = order
+            .iter()
+            .filter_map(|&old| members[old].take())
+            .collect();
+
+        for group in &mut self.groups {
+            for member in &mut group.members {
+                if let Some(&new) = new_index.get(*member as usize) {
+                    *member = new;
+                }
+            }
+            group.members.sort_unstable();
+        }
+        for member in &mut self.front {
+            if let Some(&new) = new_index.get(*member as usize) {
+                *member = new;
+            }
+        }
     }
 }
 
 impl SystemVec {
     #[cfg(any(feature = "uf2", doc))]
-    pub fn get_uf2(&self, offset: u32) -> alloc::vec::Vec<u8> {
-        let buf = self.get_bin();
-        Self::bin_to_uf2(&buf, offset)
+    pub fn get_uf2(&self, offset: u32) -> Result<alloc::vec::Vec<u8>, SizeError> {
+        let buf = self.get_bin()?;
+        Ok(Self::bin_to_uf2(&buf, offset))
     }
 
     #[cfg(any(feature = "uf2", doc))]
@@ -55,9 +129,169 @@ impl SystemVec {
         uf2::bin_to_uf2(bin, uf2::RP2040_FAMILY_ID, offset)
     }
 
-    pub fn get_bin(&self) -> alloc::vec::Vec<u8> {
-        let builder = self.capnp_builder();
-        capnp::serialize::write_message_to_words(&builder)
+    /// Serialized system with its [`Header`], as read by the badge.
+    ///
+    /// See [`SystemVec::get_bin_multi`] for the chosen encoding.
+    pub fn get_bin(&self) -> Result<alloc::vec::Vec<u8>, SizeError> {
+        Self::get_bin_multi(core::slice::from_ref(self))
+    }
+
+    /// Serializes several systems back to back, the badge switches between them.
+    ///
+    /// The systems are stored unpacked if they fit into [`DATA_REGION_SIZE`], so the badge can
+    /// read them in place. Otherwise they are packed, which needs their unpacked size to fit into
+    /// [`UNPACK_BUFFER_SIZE`]. Only the first [`MAX_SYSTEMS`](super::MAX_SYSTEMS) systems are
+    /// read by the badge.
+    pub fn get_bin_multi(systems: &[SystemVec]) -> Result<alloc::vec::Vec<u8>, SizeError> {
+        let builders: Vec<_> = systems.iter().map(Self::capnp_builder).collect();
+        let unpacked: Vec<_> = builders
+            .iter()
+            .map(capnp::serialize::write_message_to_words)
+            .collect();
+        let unpacked_len = unpacked.iter().map(|payload| payload.len()).sum::<usize>();
+        if stored_len(&unpacked) <= DATA_REGION_SIZE {
+            return Ok(encode(&unpacked, Encoding::Unpacked));
+        }
+
+        let packed: Vec<_> = builders
+            .iter()
+            .map(|builder| {
+                let mut payload = Vec::new();
+                capnp::serialize_packed::write_message(&mut payload, builder)
+                    .expect("writing to a vec cannot fail");
+                payload
+            })
+            .collect();
+        let packed_len = stored_len(&packed);
+        if packed_len > DATA_REGION_SIZE {
+            return Err(SizeError::new(
+                systems,
+                &builders,
+                packed_len,
+                DATA_REGION_SIZE,
+            ));
+        }
+        if unpacked_len > UNPACK_BUFFER_SIZE {
+            return Err(SizeError::new(
+                systems,
+                &builders,
+                unpacked_len,
+                UNPACK_BUFFER_SIZE,
+            ));
+        }
+
+        Ok(encode(&packed, Encoding::Packed))
+    }
+
+    #[cfg(any(feature = "uf2", doc))]
+    pub fn get_uf2_multi(
+        systems: &[SystemVec],
+        offset: u32,
+    ) -> Result<alloc::vec::Vec<u8>, SizeError> {
+        Ok(Self::bin_to_uf2(&Self::get_bin_multi(systems)?, offset))
+    }
+
+    /// Reads the first system of a data region written by [`SystemVec::get_bin`].
+    pub fn from_bin(bytes: &[u8]) -> Result<Self, SystemError> {
+        let mut systems = Self::from_bin_multi(bytes)?;
+        Ok(systems.swap_remove(0))
+    }
+
+    /// Reads all systems of a data region written by [`SystemVec::get_bin_multi`].
+    ///
+    /// The systems are read through [`SystemReader`], so they get the same checks as on the
+    /// badge.
+    pub fn from_bin_multi(bytes: &[u8]) -> Result<Vec<Self>, SystemError> {
+        // capnp reads whole words, so copy the bytes into aligned memory
+        let mut aligned = capnp::Word::allocate_zeroed_vec((bytes.len() + 7) / 8);
+        capnp::Word::words_to_bytes_mut(&mut aligned)[..bytes.len()].copy_from_slice(bytes);
+        let mut slice = &capnp::Word::words_to_bytes(&aligned)[..bytes.len()];
+        let mut unpacked = capnp::Word::allocate_zeroed_vec(UNPACK_BUFFER_SIZE / 8);
+        let mut buf = &mut unpacked[..];
+
+        let mut systems = Vec::new();
+        loop {
+            let reader = SystemReader::from_byte_slice_with_buffer(&mut slice, &mut buf)?;
+            let mut system = Self::from_system(&reader);
+            if let Some(root) = reader.reader() {
+                use super::system_capnp::system::Which;
+
+                system.source_id = match root.which() {
+                    Ok(Which::PkHid(Ok(id))) => {
+                        id.to_str().ok().map(|id| SourceId::PluralKit(id.into()))
+                    }
+                    Ok(Which::Pronouns(Ok(id))) => {
+                        id.to_str().ok().map(|id| SourceId::Pronouns(id.into()))
+                    }
+                    Ok(Which::SimplyPlural(Ok(id))) => {
+                        id.to_str().ok().map(|id| SourceId::SimplyPlural(id.into()))
+                    }
+                    Ok(Which::Tupperbox(Ok(id))) => {
+                        id.to_str().ok().map(|id| SourceId::Tupperbox(id.into()))
+                    }
+                    _ => None,
+                };
+                system.url = root
+                    .has_url()
+                    .then(|| root.get_url().ok()?.to_str().ok().map(String::from))
+                    .flatten();
+                if let Ok(groups) = root.get_groups() {
+                    for (group, reader) in system.groups.iter_mut().zip(groups.iter()) {
+                        group.id = reader
+                            .has_id()
+                            .then(|| reader.get_id().ok()?.to_str().ok().map(String::from))
+                            .flatten();
+                    }
+                }
+            }
+            systems.push(system);
+
+            if matches!(
+                Header::parse(slice),
+                Err(SystemError::Blank | SystemError::Truncated(_))
+            ) {
+                return Ok(systems);
+            }
+        }
+    }
+
+    /// Reads the systems from a UF2 image written by [`SystemVec::get_uf2_multi`].
+    ///
+    /// `offset` is the address of the data region the image was written for.
+    #[cfg(any(feature = "uf2", doc))]
+    pub fn from_uf2(bytes: &[u8], offset: u32) -> Result<Vec<Self>, Uf2Error> {
+        let bin = uf2::uf2_to_bin(bytes, uf2::RP2040_FAMILY_ID, offset)?;
+        Ok(Self::from_bin_multi(&bin)?)
+    }
+
+    /// Copies any [`System`] into an owned one.
+    pub fn from_system<S: System>(system: &S) -> Self {
+        let mut out = Self::new(system.name().as_ref().into());
+        match system.profile_url() {
+            Some(ProfileUrl::PluralKit(id)) => out.source_id = Some(SourceId::PluralKit(id.into())),
+            Some(ProfileUrl::Pronouns(id)) => out.source_id = Some(SourceId::Pronouns(id.into())),
+            Some(ProfileUrl::Custom(url)) => out.url = Some(url.into()),
+            None => {}
+        }
+        out.members = (0..system.member_count())
+            .map(|index| MemberStrings::from_member(&system.member(index)))
+            .collect();
+        out.groups = (0..system.group_count())
+            .map(|group| GroupVec {
+                name: system
+                    .group_name(group)
+                    .map(|name| String::from(name.as_ref()))
+                    .unwrap_or_default(),
+                id: None,
+                members: (0..system.group_len(group))
+                    .filter_map(|index| system.group_member(group, index))
+                    .collect(),
+            })
+            .collect();
+        out.front = (0..system.front_len())
+            .filter_map(|index| system.front_member(index))
+            .collect();
+        out
     }
 
     fn capnp_builder(&self) -> capnp::message::Builder<capnp::message::HeapAllocator> {
@@ -73,6 +307,37 @@ impl SystemVec {
                     SourceId::Pronouns(id) => {
                         system.set_pronouns(id.as_str().into());
                     }
+                    SourceId::SimplyPlural(id) => {
+                        system.set_simply_plural(id.as_str().into());
+                    }
+                    SourceId::Tupperbox(id) => {
+                        system.set_tupperbox(id.as_str().into());
+                    }
+                }
+            }
+            if let Some(url) = &self.url {
+                system.set_url(url.as_str().into());
+            }
+
+            if !self.groups.is_empty() {
+                let mut groups = system.reborrow().init_groups(self.groups.len() as u32);
+                for (i, group) in self.groups.iter().enumerate() {
+                    let mut out = groups.reborrow().get(i as u32);
+                    out.set_name(group.name.as_str().into());
+                    if let Some(id) = &group.id {
+                        out.set_id(id.as_str().into());
+                    }
+                    let mut members = out.init_members(group.members.len() as u32);
+                    for (j, &member) in group.members.iter().enumerate() {
+                        members.set(j as u32, member);
+                    }
+                }
+            }
+
+            if !self.front.is_empty() {
+                let mut front = system.reborrow().init_front(self.front.len() as u32);
+                for (i, &member) in self.front.iter().enumerate() {
+                    front.set(i as u32, member);
                 }
             }
 
@@ -81,12 +346,146 @@ impl SystemVec {
                 let mut out = members.reborrow().get(i as u32);
                 out.set_name(member.name.as_str().into());
                 out.set_pronouns(member.pronouns.as_str().into());
+                if let Some(avatar) = &member.avatar {
+                    let mut image = out.reborrow().init_avatar();
+                    image.set_width(avatar.width);
+                    image.set_height(avatar.height);
+                    image.set_data(&avatar.data);
+                }
+                if let Some(description) = &member.description {
+                    out.set_description(description.as_str().into());
+                }
+                if let Some(birthday) = member.birthday {
+                    let mut date = out.reborrow().init_birthday();
+                    date.set_year(birthday.year.unwrap_or(0));
+                    date.set_month(birthday.month);
+                    date.set_day(birthday.day);
+                }
+                match member.color {
+                    Some(rgb) => out.reborrow().init_color().set_rgb(rgb),
+                    None => out.reborrow().init_color().set_none(()),
+                }
+                if !member.fields.is_empty() {
+                    let mut fields = out.reborrow().init_fields(member.fields.len() as u32);
+                    for (i, field) in member.fields.iter().enumerate() {
+                        let mut out = fields.reborrow().get(i as u32);
+                        out.set_name(field.name.as_str().into());
+                        out.set_value(field.value.as_str().into());
+                    }
+                }
+                if let Some(id) = &member.id {
+                    out.set_id(id.as_str().into());
+                }
+                if let Some(display_name) = &member.display_name {
+                    out.set_display_name(display_name.as_str().into());
+                }
+                if !member.pronoun_list.is_empty() {
+                    let mut list = out
+                        .reborrow()
+                        .init_pronoun_list(member.pronoun_list.len() as u32);
+                    for (i, pronouns) in member.pronoun_list.iter().enumerate() {
+                        list.set(i as u32, pronouns.as_str().into());
+                    }
+                }
+                out.set_private(member.private);
             }
         }
         builder
     }
 }
 
+/// Bytes taken by `payloads` with their headers in the data region.
+fn stored_len(payloads: &[Vec<u8>]) -> usize {
+    payloads
+        .iter()
+        .map(|payload| Header::for_payload(payload, Encoding::Unpacked).stored_len())
+        .sum()
+}
+
+/// Prefixes every payload with its header and pads it to whole words.
+///
+/// A zeroed header follows the last system if it fits, so systems left in flash from an earlier,
+/// longer image are not read.
+fn encode(payloads: &[Vec<u8>], encoding: Encoding) -> Vec<u8> {
+    let mut bin = Vec::new();
+    for payload in payloads {
+        let header = Header::for_payload(payload, encoding);
+        bin.extend_from_slice(&header.to_bytes());
+        bin.extend_from_slice(payload);
+        bin.resize(
+            bin.len() + header.stored_len() - Header::LEN - payload.len(),
+            0,
+        );
+    }
+    if bin.len() + Header::LEN <= DATA_REGION_SIZE {
+        bin.resize(bin.len() + Header::LEN, 0);
+    }
+    bin
+}
+
+/// Serialized systems do not fit onto the badge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeError {
+    /// Size of the serialized systems in bytes.
+    pub size: usize,
+    /// Bytes available, [`DATA_REGION_SIZE`] or [`UNPACK_BUFFER_SIZE`].
+    pub budget: usize,
+    /// Name and unpacked size of the largest members, largest first.
+    pub largest_members: Vec<(String, usize)>,
+}
+
+impl SizeError {
+    /// Number of members listed in [`SizeError::largest_members`].
+    const LISTED_MEMBERS: usize = 5;
+
+    fn new(
+        systems: &[SystemVec],
+        builders: &[capnp::message::Builder<capnp::message::HeapAllocator>],
+        size: usize,
+        budget: usize,
+    ) -> Self {
+        let mut largest_members = Vec::new();
+        for (system, builder) in systems.iter().zip(builders) {
+            let Ok(members) = builder
+                .get_root_as_reader::<super::system_capnp::system::Reader>()
+                .and_then(|reader| reader.get_members())
+            else {
+                continue;
+            };
+            for (member, reader) in system.members.iter().zip(members.iter()) {
+                let words = reader.total_size().map_or(0, |size| size.word_count);
+                largest_members.push((String::from(member.shown_name()), words as usize * 8));
+            }
+        }
+        largest_members.sort_by(|a, b| b.1.cmp(&a.1));
+        largest_members.truncate(Self::LISTED_MEMBERS);
+
+        Self {
+            size,
+            budget,
+            largest_members,
+        }
+    }
+}
+
+impl core::fmt::Display for SizeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "system is {} bytes, budget is {}K",
+            self.size,
+            self.budget / 1024
+        )?;
+        for (i, (name, size)) in self.largest_members.iter().enumerate() {
+            let sep = if i == 0 { "; largest members: " } else { ", " };
+            write!(f, "{}{} ({} bytes)", sep, name, size)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for SizeError {}
+
 impl System for SystemVec {
     fn name(&self) -> &str {
         &self.name
@@ -99,13 +498,109 @@ impl System for SystemVec {
     fn member(&self, index: usize) -> &MemberStrings {
         &self.members[index]
     }
+
+    fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    fn group_name(&self, group: usize) -> Option<&str> {
+        self.groups.get(group).map(|group| group.name.as_str())
+    }
+
+    fn group_len(&self, group: usize) -> usize {
+        self.groups
+            .get(group)
+            .map_or(0, |group| group.members.len())
+    }
+
+    fn group_member(&self, group: usize, index: usize) -> Option<u16> {
+        self.groups.get(group)?.members.get(index).copied()
+    }
+
+    fn front_len(&self) -> usize {
+        self.front.len()
+    }
+
+    fn front_member(&self, index: usize) -> Option<u16> {
+        self.front.get(index).copied()
+    }
+
+    fn profile_url(&self) -> Option<ProfileUrl<'_>> {
+        if let Some(url) = &self.url {
+            return Some(ProfileUrl::Custom(url));
+        }
+
+        match self.source_id.as_ref()? {
+            SourceId::PluralKit(hid) => Some(ProfileUrl::PluralKit(hid)),
+            SourceId::Pronouns(id) => Some(ProfileUrl::Pronouns(id)),
+            // neither has public profile pages
+            SourceId::SimplyPlural(_) | SourceId::Tupperbox(_) => None,
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct MemberStrings {
     pub name: String,
     pub pronouns: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub avatar: Option<BitmapVec>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub birthday: Option<Birthday>,
+    /// Colour as `0xRRGGBB`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub color: Option<u32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "alloc::vec::Vec::is_empty")
+    )]
+    pub fields: alloc::vec::Vec<MemberField>,
+    /// Stable id of the member in its source
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub id: Option<String>,
+    /// Name shown instead of `name` if set
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub display_name: Option<String>,
+    /// All pronoun sets, `pronouns` holds the preferred one
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "alloc::vec::Vec::is_empty")
+    )]
+    pub pronoun_list: alloc::vec::Vec<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "core::ops::Not::not")
+    )]
+    pub private: bool,
+}
+
+/// Additional profile text of a member.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberField {
+    pub name: String,
+    pub value: String,
 }
 
 impl Member for MemberStrings {
@@ -116,12 +611,137 @@ impl Member for MemberStrings {
     fn pronouns(&self) -> &str {
         &self.pronouns
     }
+
+    fn avatar<'a>(&'a self) -> Option<Bitmap<'a>> {
+        self.avatar.as_ref().and_then(BitmapVec::as_bitmap)
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn birthday(&self) -> Option<Birthday> {
+        self.birthday
+    }
+
+    fn color(&self) -> Option<u32> {
+        self.color
+    }
+
+    fn field_count(&self) -> usize {
+        self.fields.len()
+    }
+
+    fn field(&self, index: usize) -> Option<(&str, &str)> {
+        self.fields
+            .get(index)
+            .map(|field| (field.name.as_str(), field.value.as_str()))
+    }
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    fn display_name(&self) -> Option<&str> {
+        self.display_name.as_deref()
+    }
+
+    fn pronoun_count(&self) -> usize {
+        self.pronoun_list.len()
+    }
+
+    fn pronoun_entry(&self, index: usize) -> Option<&str> {
+        self.pronoun_list.get(index).map(String::as_str)
+    }
+
+    fn is_private(&self) -> bool {
+        self.private
+    }
+}
+
+impl MemberStrings {
+    /// Copies any [`Member`] into an owned one.
+    pub fn from_member<M: Member>(member: &M) -> Self {
+        Self {
+            name: member.name().as_ref().into(),
+            pronouns: member.pronouns().as_ref().into(),
+            avatar: member.avatar().map(|avatar| BitmapVec {
+                width: avatar.width,
+                height: avatar.height,
+                data: avatar.data()[..Bitmap::stride(avatar.width) * avatar.height as usize]
+                    .to_vec(),
+            }),
+            description: member.description().map(String::from),
+            birthday: member.birthday(),
+            color: member.color(),
+            fields: (0..member.field_count())
+                .filter_map(|index| member.field(index))
+                .map(|(name, value)| MemberField {
+                    name: name.into(),
+                    value: value.into(),
+                })
+                .collect(),
+            id: member.id().map(|id| String::from(id.as_ref())),
+            display_name: member
+                .display_name()
+                .map(|name| String::from(name.as_ref())),
+            pronoun_list: (0..member.pronoun_count())
+                .filter_map(|index| {
+                    member
+                        .pronoun_entry(index)
+                        .map(|p| String::from(p.as_ref()))
+                })
+                .collect(),
+            private: member.is_private(),
+        }
+    }
+
+    /// Name shown on the badge, the display name if set.
+    pub fn shown_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Owned 1-bit image, see [`Bitmap`] for the data layout.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitmapVec {
+    pub width: u16,
+    pub height: u16,
+    pub data: alloc::vec::Vec<u8>,
+}
+
+impl BitmapVec {
+    /// Creates an image with all bits cleared.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            data: alloc::vec![0; Bitmap::stride(width) * height as usize],
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: u16, y: u16, value: bool) {
+        let byte = &mut self.data[y as usize * Bitmap::stride(self.width) + x as usize / 8];
+        if value {
+            *byte |= 0x80 >> (x % 8);
+        } else {
+            *byte &= !(0x80 >> (x % 8));
+        }
+    }
+
+    /// Returns `None` if the data does not match the size of the image.
+    pub fn as_bitmap(&self) -> Option<Bitmap<'_>> {
+        Bitmap::new(self.width, self.height, &self.data)
+    }
 }
 
 #[cfg(feature = "uf2")]
 mod uf2 {
     use alloc::vec::Vec;
 
+    use crate::system::{SystemError, DATA_REGION_SIZE};
+
     /// copied and modified from the uf2 crate
 
     const UF2_MAGIC_START0: u32 = 0x0A324655; // "UF2\n"
@@ -130,6 +750,95 @@ mod uf2 {
 
     pub const RP2040_FAMILY_ID: u32 = 0xe48bff56;
 
+    const BLOCK_SIZE: usize = 512;
+    /// Largest payload of a block, the rest is taken by the header and the end magic.
+    const MAX_PAYLOAD: usize = 476;
+    /// Block flag marking `family_id` as valid.
+    const FLAG_FAMILY_ID: u32 = 0x2000;
+
+    /// Errors reading a UF2 image.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Uf2Error {
+        /// Image is empty or not a whole number of 512 byte blocks.
+        Length(usize),
+        /// Block has invalid magic numbers or payload size.
+        Block(usize),
+        /// Block is written for another chip.
+        FamilyId(u32),
+        /// Block is outside of the data region.
+        Address(u32),
+        /// Reassembled data region holds no valid system.
+        System(SystemError),
+    }
+
+    impl From<SystemError> for Uf2Error {
+        fn from(err: SystemError) -> Self {
+            Self::System(err)
+        }
+    }
+
+    impl core::fmt::Display for Uf2Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Self::Length(len) => write!(f, "{} bytes are no whole number of uf2 blocks", len),
+                Self::Block(block) => write!(f, "uf2 block {} is malformed", block),
+                Self::FamilyId(id) => write!(f, "uf2 family id {:#010x} is not an RP2040", id),
+                Self::Address(addr) => {
+                    write!(f, "address {:#010x} outside of the data region", addr)
+                }
+                Self::System(err) => write!(f, "{}", err),
+            }
+        }
+    }
+
+    impl core::error::Error for Uf2Error {
+        fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+            match self {
+                Self::System(err) => Some(err),
+                _ => None,
+            }
+        }
+    }
+
+    /// Reassembles the data region at `start_addr` from the blocks of a UF2 image.
+    ///
+    /// Bytes not covered by any block read as erased flash.
+    pub fn uf2_to_bin(bytes: &[u8], family_id: u32, start_addr: u32) -> Result<Vec<u8>, Uf2Error> {
+        if bytes.is_empty() || bytes.len() % BLOCK_SIZE != 0 {
+            return Err(Uf2Error::Length(bytes.len()));
+        }
+
+        let mut bin = Vec::new();
+        for (index, block) in bytes.chunks_exact(BLOCK_SIZE).enumerate() {
+            let word =
+                |offset: usize| u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap());
+            let len = word(16) as usize;
+            if word(0) != UF2_MAGIC_START0
+                || word(4) != UF2_MAGIC_START1
+                || word(BLOCK_SIZE - 4) != UF2_MAGIC_END
+                || len > MAX_PAYLOAD
+            {
+                return Err(Uf2Error::Block(index));
+            }
+            if word(8) & FLAG_FAMILY_ID == 0 || word(28) != family_id {
+                return Err(Uf2Error::FamilyId(word(28)));
+            }
+
+            let addr = word(12);
+            let start = addr
+                .checked_sub(start_addr)
+                .map(|start| start as usize)
+                .filter(|start| start + len <= DATA_REGION_SIZE)
+                .ok_or(Uf2Error::Address(addr))?;
+            if bin.len() < start + len {
+                bin.resize(start + len, 0xff);
+            }
+            bin[start..start + len].copy_from_slice(&block[32..32 + len]);
+        }
+
+        Ok(bin)
+    }
+
     pub fn bin_to_uf2(bytes: &[u8], family_id: u32, app_start_addr: u32) -> Vec<u8> {
         let datapadding = 512 - 256 - 32 - 4;
         let nblocks: u32 = ((bytes.len() + 255) / 256) as u32;
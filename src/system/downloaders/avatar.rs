@@ -0,0 +1,75 @@
+//! Conversion of avatar images into the 1-bit format of the badge.
+
+use alloc::vec::Vec;
+
+use crate::system::BitmapVec;
+
+/// Width and height of avatars, fits a single member cell of the 296x128 panel.
+pub const AVATAR_SIZE: u16 = 64;
+
+/// Downloads the image at `url` and converts it with [`convert`].
+///
+/// Avatars are optional, so any failure is reported as `None` instead of failing the whole
+/// download.
+pub(crate) async fn fetch(client: &super::HttpClient, url: &str) -> Option<BitmapVec> {
+    let bytes = client.get(url).await.ok()?;
+
+    convert(&bytes)
+}
+
+/// Decodes an image, scales and crops it to [`AVATAR_SIZE`] and dithers it to 1-bit.
+pub fn convert(bytes: &[u8]) -> Option<BitmapVec> {
+    let image = image::load_from_memory(bytes).ok()?;
+    let image = image
+        .resize_to_fill(
+            AVATAR_SIZE as u32,
+            AVATAR_SIZE as u32,
+            image::imageops::FilterType::Triangle,
+        )
+        .to_luma_alpha8();
+
+    // Transparent areas are shown as paper, so blend the image onto white
+    let luma: Vec<u8> = image
+        .pixels()
+        .map(|pixel| {
+            let [luma, alpha] = pixel.0;
+            ((luma as u16 * alpha as u16 + 255 * (255 - alpha as u16)) / 255) as u8
+        })
+        .collect();
+
+    Some(dither(image.width() as u16, image.height() as u16, &luma))
+}
+
+/// Floyd–Steinberg dithering of an 8-bit grayscale image, dark pixels become set bits.
+pub fn dither(width: u16, height: u16, luma: &[u8]) -> BitmapVec {
+    let (w, h) = (width as usize, height as usize);
+    debug_assert!(luma.len() >= w * h);
+
+    let mut buf: Vec<i16> = luma.iter().map(|&l| l as i16).collect();
+    let mut bitmap = BitmapVec::new(width, height);
+
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let old = buf[i];
+            let ink = old < 128;
+            bitmap.set_pixel(x as u16, y as u16, ink);
+
+            let err = old - if ink { 0 } else { 255 };
+            if x + 1 < w {
+                buf[i + 1] += err * 7 / 16;
+            }
+            if y + 1 < h {
+                if x > 0 {
+                    buf[i + w - 1] += err * 3 / 16;
+                }
+                buf[i + w] += err * 5 / 16;
+                if x + 1 < w {
+                    buf[i + w + 1] += err / 16;
+                }
+            }
+        }
+    }
+
+    bitmap
+}
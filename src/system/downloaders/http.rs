@@ -0,0 +1,347 @@
+//! Request layer shared by the downloaders.
+//!
+//! Failed requests are retried with jittered exponential backoff, waits asked for by the server
+//! through `Retry-After` or PluralKit's `retry_after` are honoured, and an exhausted
+//! `X-RateLimit-Remaining` pauses the next request until `X-RateLimit-Reset`. Callers can follow
+//! along through [`Progress`].
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+
+use super::DownloadError;
+
+/// Pause once the rate limit is used up without a known reset time, PluralKit refills every
+/// second.
+const RATE_LIMIT_PAUSE_MS: u32 = 1000;
+
+/// How often and how long failed requests are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, 0 disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every further one.
+    pub base_delay_ms: u32,
+    /// Longest delay. Servers asking for a longer wait fail the request instead.
+    pub max_delay_ms: u32,
+}
+
+impl RetryPolicy {
+    /// Never retries.
+    pub const NONE: Self = Self {
+        max_retries: 0,
+        base_delay_ms: 0,
+        max_delay_ms: 0,
+    };
+
+    fn backoff(&self, attempt: u32) -> u32 {
+        let exp = self
+            .base_delay_ms
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay_ms);
+        // equal jitter, keeps clients that failed together from retrying together
+        exp / 2 + jitter(exp / 2 + 1)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 4,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+/// Progress of a download, reported to the callback set with [`HttpClient::with_progress`].
+#[derive(Debug)]
+pub enum Progress<'a> {
+    /// A request is sent.
+    Request { url: &'a str },
+    /// A request failed and is sent again after `delay_ms`.
+    Retry {
+        url: &'a str,
+        attempt: u32,
+        delay_ms: u32,
+        error: &'a DownloadError,
+    },
+    /// Avatars downloaded so far.
+    Avatars { done: usize, total: usize },
+    /// Private members left out of `system`, see [`crate::system::PrivacyPolicy`].
+    Skipped {
+        system: &'a str,
+        names: &'a [String],
+    },
+}
+
+pub type ProgressFn = Arc<dyn Fn(&Progress<'_>) + Send + Sync>;
+
+/// Rate limit state, shared by all clients of one [`super::GenericDownloader`].
+#[derive(Debug, Default)]
+pub(crate) struct Throttle {
+    /// Wait before the next request in milliseconds, 0 while requests are left.
+    pause_ms: AtomicU32,
+}
+
+/// HTTP client with retries and rate limit handling.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    retry: RetryPolicy,
+    progress: Option<ProgressFn>,
+    throttle: Arc<Throttle>,
+}
+
+impl core::fmt::Debug for HttpClient {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HttpClient")
+            .field("client", &self.client)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpClient {
+    pub fn new(useragent: &str) -> Self {
+        Self {
+            client: super::build_client(useragent),
+            retry: RetryPolicy::default(),
+            progress: None,
+            throttle: Arc::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_progress(
+        mut self,
+        progress: impl Fn(&Progress<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    pub(crate) fn with_progress_fn(mut self, progress: Option<ProgressFn>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub(crate) fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    pub(crate) fn set_useragent(&mut self, useragent: &str) {
+        self.client = super::build_client(useragent);
+    }
+
+    pub(crate) fn report(&self, progress: Progress<'_>) {
+        if let Some(callback) = &self.progress {
+            callback(&progress);
+        }
+    }
+
+    /// Requests `url` and parses the JSON response.
+    pub(crate) async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<T, DownloadError> {
+        self.get_json_authorized(url, None).await
+    }
+
+    /// Like [`Self::get_json`], sending `token` as `Authorization` header if set.
+    pub(crate) async fn get_json_authorized<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        token: Option<&str>,
+    ) -> Result<T, DownloadError> {
+        let bytes = self.request(url, token).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Requests `url`, retrying transient failures according to the [`RetryPolicy`].
+    pub(crate) async fn get(&self, url: &str) -> Result<Vec<u8>, DownloadError> {
+        self.request(url, None).await
+    }
+
+    /// Like [`Self::get`], sending `token` as `Authorization` header if set.
+    pub(crate) async fn get_authorized(
+        &self,
+        url: &str,
+        token: Option<&str>,
+    ) -> Result<Vec<u8>, DownloadError> {
+        self.request(url, token).await
+    }
+
+    async fn request(&self, url: &str, token: Option<&str>) -> Result<Vec<u8>, DownloadError> {
+        let mut attempt = 0;
+        loop {
+            let pause_ms = self.throttle.pause_ms.swap(0, Ordering::Relaxed);
+            if pause_ms > 0 {
+                sleep(pause_ms.min(self.retry.max_delay_ms)).await;
+            }
+
+            self.report(Progress::Request { url });
+            let mut request = self.client.get(url);
+            if let Some(token) = token {
+                request = request.header("Authorization", token);
+            }
+            let (error, retry_after) = match request.send().await {
+                Ok(resp) => {
+                    self.track(&resp);
+                    let status = resp.status();
+                    if status.is_success() {
+                        return Ok(resp.bytes().await?.to_vec());
+                    }
+
+                    let header = retry_after_header(&resp);
+                    let body = resp.bytes().await.unwrap_or_default();
+                    (
+                        DownloadError::from_status(status.as_u16()),
+                        header.or_else(|| retry_after_body(&body)),
+                    )
+                }
+                Err(err) => (DownloadError::from(err), None),
+            };
+
+            if attempt >= self.retry.max_retries || !is_transient(&error) {
+                return Err(error);
+            }
+            attempt += 1;
+            let delay_ms = match retry_after {
+                // waiting less than asked only gets the client limited for longer
+                Some(ms) if ms > self.retry.max_delay_ms => return Err(error),
+                Some(ms) => ms,
+                None => self.retry.backoff(attempt),
+            };
+            self.report(Progress::Retry {
+                url,
+                attempt,
+                delay_ms,
+                error: &error,
+            });
+            sleep(delay_ms).await;
+        }
+    }
+
+    fn track(&self, resp: &reqwest::Response) {
+        let header = |name: &str| {
+            resp.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok()?.trim().parse::<u64>().ok())
+        };
+        if header("X-RateLimit-Remaining") != Some(0) {
+            return;
+        }
+
+        // the reset is a unix timestamp in seconds
+        let pause_ms = match (header("X-RateLimit-Reset"), unix_time_ms()) {
+            (Some(reset), Some(now)) => reset.saturating_mul(1000).saturating_sub(now),
+            _ => RATE_LIMIT_PAUSE_MS as u64,
+        };
+        self.throttle
+            .pause_ms
+            .store(pause_ms.min(u32::MAX as u64) as u32, Ordering::Relaxed);
+    }
+}
+
+fn is_transient(error: &DownloadError) -> bool {
+    match error {
+        DownloadError::RateLimited => true,
+        DownloadError::Status(status) => matches!(status, 500 | 502 | 503 | 504),
+        DownloadError::Network(err) => {
+            // the browser does not tell connection failures apart
+            #[cfg(not(target_family = "wasm"))]
+            if err.is_connect() {
+                return true;
+            }
+            err.is_timeout()
+        }
+        _ => false,
+    }
+}
+
+/// `Retry-After` in seconds, HTTP dates are not supported.
+fn retry_after_header(resp: &reqwest::Response) -> Option<u32> {
+    let seconds: u32 = resp
+        .headers()
+        .get("Retry-After")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(seconds.saturating_mul(1000))
+}
+
+/// PluralKit sends the wait in milliseconds in the body of 429 responses.
+fn retry_after_body(body: &[u8]) -> Option<u32> {
+    #[derive(serde::Deserialize)]
+    struct RateLimited {
+        retry_after: u32,
+    }
+
+    Some(
+        serde_json::from_slice::<RateLimited>(body)
+            .ok()?
+            .retry_after,
+    )
+}
+
+/// Milliseconds since the unix epoch.
+#[cfg(not(target_family = "wasm"))]
+fn unix_time_ms() -> Option<u64> {
+    extern crate std;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    Some(now.as_millis() as u64)
+}
+
+/// Milliseconds since the unix epoch, from the browser.
+#[cfg(all(target_family = "wasm", feature = "wasm"))]
+fn unix_time_ms() -> Option<u64> {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = Date)]
+        fn now() -> f64;
+    }
+
+    Some(now() as u64)
+}
+
+/// Without a clock the reset time cannot be used, see [`RATE_LIMIT_PAUSE_MS`].
+#[cfg(all(target_family = "wasm", not(feature = "wasm")))]
+fn unix_time_ms() -> Option<u64> {
+    None
+}
+
+async fn sleep(ms: u32) {
+    futures_timer::Delay::new(Duration::from_millis(ms as u64)).await
+}
+
+/// Random number below `max`, xorshift is plenty for spreading retries.
+fn jitter(max: u32) -> u32 {
+    static STATE: AtomicU32 = AtomicU32::new(0);
+
+    let mut state = STATE.load(Ordering::Relaxed);
+    if state == 0 {
+        // the address differs between runs with ASLR, good enough as a seed
+        state = (&STATE as *const AtomicU32 as usize as u32) | 1;
+    }
+    state ^= state << 13;
+    state ^= state >> 17;
+    state ^= state << 5;
+    STATE.store(state, Ordering::Relaxed);
+
+    state % max.max(1)
+}
@@ -1,4 +1,11 @@
 use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+
+#[cfg(feature = "avatars")]
+mod avatar;
+mod http;
+
+pub use http::{HttpClient, Progress, ProgressFn, RetryPolicy};
 
 #[cfg(feature = "downloader-pk")]
 mod pk;
@@ -10,13 +17,25 @@ pub use pk::PkDownloader;
 mod pronouns;
 
 #[cfg(feature = "downloader-pronouns")]
-use pronouns::PronounsDownloader;
+pub use pronouns::{PronounsDownloader, StatusPolicy};
+
+#[cfg(feature = "downloader-simplyplural")]
+mod simply_plural;
+
+#[cfg(feature = "downloader-simplyplural")]
+pub use simply_plural::SimplyPluralDownloader;
 
-use super::SystemVec;
+#[cfg(feature = "downloader-tupperbox")]
+mod tupperbox;
+
+#[cfg(feature = "downloader-tupperbox")]
+pub use tupperbox::TupperboxDownloader;
+
+use super::{PrivacyPolicy, SystemVec};
 
 pub trait Downloader {
     async fn set_useragent(&mut self, _useragent: impl ToString) {}
-    async fn get(&self, args: impl AsRef<str>) -> Result<SystemVec, reqwest::Error>;
+    async fn get(&self, args: impl AsRef<str>) -> Result<SystemVec, DownloadError>;
 }
 
 #[non_exhaustive]
@@ -24,8 +43,17 @@ pub trait Downloader {
 pub enum Source {
     #[cfg(feature = "downloader-pk")]
     PluralKit,
+    /// File written by PluralKit's `pk;export` command.
+    #[cfg(feature = "downloader-pk")]
+    PluralKitExport,
     #[cfg(feature = "downloader-pronouns")]
     Pronouns,
+    /// JSON export of Simply Plural.
+    #[cfg(feature = "downloader-simplyplural")]
+    SimplyPlural,
+    /// File written by Tupperbox's `tul!export` command.
+    #[cfg(feature = "downloader-tupperbox")]
+    Tupperbox,
 }
 
 impl Source {
@@ -33,8 +61,14 @@ impl Source {
         match self {
             #[cfg(feature = "downloader-pk")]
             Self::PluralKit => "pk",
+            #[cfg(feature = "downloader-pk")]
+            Self::PluralKitExport => "pkexport",
             #[cfg(feature = "downloader-pronouns")]
             Self::Pronouns => "pronouns",
+            #[cfg(feature = "downloader-simplyplural")]
+            Self::SimplyPlural => "sp",
+            #[cfg(feature = "downloader-tupperbox")]
+            Self::Tupperbox => "tupperbox",
         }
     }
 }
@@ -44,8 +78,14 @@ impl core::fmt::Display for Source {
         match self {
             #[cfg(feature = "downloader-pk")]
             Self::PluralKit => write!(f, "PluralKit"),
+            #[cfg(feature = "downloader-pk")]
+            Self::PluralKitExport => write!(f, "PluralKit export"),
             #[cfg(feature = "downloader-pronouns")]
             Self::Pronouns => write!(f, "Pronouns"),
+            #[cfg(feature = "downloader-simplyplural")]
+            Self::SimplyPlural => write!(f, "SimplyPlural"),
+            #[cfg(feature = "downloader-tupperbox")]
+            Self::Tupperbox => write!(f, "Tupperbox"),
         }
     }
 }
@@ -70,8 +110,14 @@ impl core::str::FromStr for Source {
         match s {
             #[cfg(feature = "downloader-pk")]
             "pk" | "PluralKit" => Ok(Self::PluralKit),
+            #[cfg(feature = "downloader-pk")]
+            "pkexport" | "PluralKitExport" => Ok(Self::PluralKitExport),
             #[cfg(feature = "downloader-pronouns")]
             "pronouns" => Ok(Self::Pronouns),
+            #[cfg(feature = "downloader-simplyplural")]
+            "sp" | "SimplyPlural" => Ok(Self::SimplyPlural),
+            #[cfg(feature = "downloader-tupperbox")]
+            "tupperbox" | "Tupperbox" => Ok(Self::Tupperbox),
             _ => Err(ParseError),
         }
     }
@@ -83,8 +129,14 @@ impl clap::ValueEnum for Source {
         &[
             #[cfg(feature = "downloader-pk")]
             Self::PluralKit,
+            #[cfg(feature = "downloader-pk")]
+            Self::PluralKitExport,
             #[cfg(feature = "downloader-pronouns")]
             Self::Pronouns,
+            #[cfg(feature = "downloader-simplyplural")]
+            Self::SimplyPlural,
+            #[cfg(feature = "downloader-tupperbox")]
+            Self::Tupperbox,
         ]
     }
 
@@ -92,8 +144,16 @@ impl clap::ValueEnum for Source {
         Some(match self {
             #[cfg(feature = "downloader-pk")]
             Self::PluralKit => clap::builder::PossibleValue::new("PluralKit").alias("pk"),
+            #[cfg(feature = "downloader-pk")]
+            Self::PluralKitExport => {
+                clap::builder::PossibleValue::new("PluralKitExport").alias("pkexport")
+            }
             #[cfg(feature = "downloader-pronouns")]
             Self::Pronouns => clap::builder::PossibleValue::new("Pronouns"),
+            #[cfg(feature = "downloader-simplyplural")]
+            Self::SimplyPlural => clap::builder::PossibleValue::new("SimplyPlural").alias("sp"),
+            #[cfg(feature = "downloader-tupperbox")]
+            Self::Tupperbox => clap::builder::PossibleValue::new("Tupperbox"),
         })
     }
 }
@@ -109,45 +169,341 @@ impl core::fmt::Display for ParseError {
 
 impl core::error::Error for ParseError {}
 
+/// Reasons a download failed.
+#[derive(Debug)]
+pub enum DownloadError {
+    /// The system does not exist.
+    NotFound,
+    /// The system exists, but does not share the requested data.
+    Private,
+    /// Too many requests, the server asks to try again later.
+    RateLimited,
+    /// Any other HTTP error status.
+    Status(u16),
+    /// The response did not have the expected layout.
+    Malformed(serde_json::Error),
+    /// The server could not be reached.
+    Network(reqwest::Error),
+}
+
+impl DownloadError {
+    /// Maps an HTTP error status, PluralKit and pronouns.cc use the same codes.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            404 => Self::NotFound,
+            401 | 403 => Self::Private,
+            429 => Self::RateLimited,
+            status => Self::Status(status),
+        }
+    }
+
+    /// HTTP status the server answered with, if any.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::NotFound => Some(404),
+            Self::Private => Some(403),
+            Self::RateLimited => Some(429),
+            Self::Status(status) => Some(*status),
+            Self::Malformed(_) => None,
+            Self::Network(err) => err.status().map(|status| status.as_u16()),
+        }
+    }
+}
+
+impl core::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "system not found"),
+            Self::Private => write!(f, "system is private"),
+            Self::RateLimited => write!(f, "rate limited, try again later"),
+            Self::Status(status) => write!(f, "server answered with HTTP status {}", status),
+            Self::Malformed(err) => write!(f, "malformed response: {}", err),
+            Self::Network(err) => write!(f, "network error: {}", err),
+        }
+    }
+}
+
+impl core::error::Error for DownloadError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Malformed(err) => Some(err),
+            Self::Network(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(err: reqwest::Error) -> Self {
+        match err.status() {
+            Some(status) => Self::from_status(status.as_u16()),
+            None => Self::Network(err),
+        }
+    }
+}
+
+impl From<serde_json::Error> for DownloadError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Malformed(err)
+    }
+}
+
 pub struct GenericDownloader {
     pub useragent: String,
+    pub retry: RetryPolicy,
+    /// Called with the [`Progress`] of every download.
+    pub progress: Option<ProgressFn>,
+    /// Base url of the PluralKit API, `None` for the public instance.
+    pub pk_base_url: Option<String>,
+    /// Base url of the pronouns.cc API, `None` for the public instance.
+    pub pronouns_base_url: Option<String>,
+    /// `pk;token` of the system, downloads include private members with it.
+    pub pk_token: Option<String>,
+    /// Private members kept on the badge, and the private fields and groups of PluralKit systems.
+    pub privacy: PrivacyPolicy,
+    /// Also download the current fronters of PluralKit systems.
+    pub pk_front: bool,
+    /// Download the avatars of PluralKit members, each takes a request and 512 bytes of flash.
+    /// Only used with the `avatars` feature.
+    pub avatars: bool,
+    /// Entries of pronouns.cc users kept on the badge.
+    #[cfg(feature = "downloader-pronouns")]
+    pub pronouns_policy: StatusPolicy,
+    /// Shared by all downloads, so batches respect the rate limit together.
+    throttle: Arc<http::Throttle>,
 }
 
 impl GenericDownloader {
     pub fn new() -> Self {
         Self {
             useragent: "sysbadge downloader".to_string(),
+            retry: RetryPolicy::default(),
+            progress: None,
+            pk_base_url: None,
+            pronouns_base_url: None,
+            pk_token: None,
+            privacy: PrivacyPolicy::public(),
+            pk_front: false,
+            avatars: true,
+            #[cfg(feature = "downloader-pronouns")]
+            pronouns_policy: StatusPolicy::default(),
+            throttle: Default::default(),
         }
     }
 
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_progress(
+        mut self,
+        progress: impl Fn(&Progress<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    pub fn with_pk_base_url(mut self, base_url: impl ToString) -> Self {
+        self.pk_base_url = Some(base_url.to_string());
+        self
+    }
+
+    pub fn with_pronouns_base_url(mut self, base_url: impl ToString) -> Self {
+        self.pronouns_base_url = Some(base_url.to_string());
+        self
+    }
+
+    pub fn with_pk_token(mut self, token: impl ToString) -> Self {
+        self.pk_token = Some(token.to_string());
+        self
+    }
+
+    pub fn with_privacy(mut self, policy: PrivacyPolicy) -> Self {
+        self.privacy = policy;
+        self
+    }
+
+    pub fn with_pk_front(mut self) -> Self {
+        self.pk_front = true;
+        self
+    }
+
+    pub fn without_avatars(mut self) -> Self {
+        self.avatars = false;
+        self
+    }
+
+    #[cfg(feature = "downloader-pronouns")]
+    pub fn with_pronouns_policy(mut self, policy: StatusPolicy) -> Self {
+        self.pronouns_policy = policy;
+        self
+    }
+
+    /// Request layer shared by the downloads.
+    fn client(&self) -> HttpClient {
+        HttpClient::new(&self.useragent)
+            .with_retry(self.retry)
+            .with_progress_fn(self.progress.clone())
+            .with_throttle(self.throttle.clone())
+    }
+
+    /// Removes the members [`Self::privacy`] does not allow, reporting their names.
+    fn skip_private(&self, mut system: SystemVec) -> SystemVec {
+        skip_private(&mut system, &self.privacy, |progress| {
+            if let Some(callback) = &self.progress {
+                callback(&progress);
+            }
+        });
+        system
+    }
+
+    /// Downloads the system `id` from `source`.
+    ///
+    /// For the export sources `id` is the link to the export file, as sent by PluralKit,
+    /// Simply Plural or Tupperbox.
     pub async fn get(
         &self,
         source: Source,
         id: impl AsRef<str>,
-    ) -> Result<SystemVec, reqwest::Error> {
+    ) -> Result<SystemVec, DownloadError> {
         match source {
             #[cfg(feature = "downloader-pk")]
             Source::PluralKit => self.get_pk(id).await,
+            #[cfg(feature = "downloader-pk")]
+            Source::PluralKitExport => self.get_pk_export(id).await,
             #[cfg(feature = "downloader-pronouns")]
             Source::Pronouns => self.get_pronouns(id).await,
+            #[cfg(feature = "downloader-simplyplural")]
+            Source::SimplyPlural => self.get_simply_plural(id).await,
+            #[cfg(feature = "downloader-tupperbox")]
+            Source::Tupperbox => self.get_tupperbox(id).await,
         }
     }
 
     #[cfg(feature = "downloader-pk")]
-    pub async fn get_pk(&self, id: impl AsRef<str>) -> Result<SystemVec, reqwest::Error> {
-        let mut downloader = PkDownloader::new();
-        downloader.set_useragent(&self.useragent).await;
+    pub async fn get_pk(&self, id: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        let mut downloader = PkDownloader::new()
+            .with_privacy(self.privacy.clone())
+            .with_client(self.client());
+        if let Some(base_url) = &self.pk_base_url {
+            downloader = downloader.with_base_url(base_url);
+        }
+        if let Some(token) = &self.pk_token {
+            downloader = downloader.with_token(token);
+        }
+        if self.pk_front {
+            downloader = downloader.with_front();
+        }
+        #[cfg(feature = "avatars")]
+        if !self.avatars {
+            downloader = downloader.without_avatars();
+        }
         downloader.get(id).await
     }
 
+    #[cfg(feature = "downloader-pk")]
+    pub async fn get_pk_export(&self, url: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        #[allow(unused_mut)]
+        let mut downloader = PkDownloader::new()
+            .with_privacy(self.privacy.clone())
+            .with_client(self.client());
+        #[cfg(feature = "avatars")]
+        if !self.avatars {
+            downloader = downloader.without_avatars();
+        }
+        downloader.get_export(url).await
+    }
+
+    /// Reads a system from a file instead of the network.
+    ///
+    /// PluralKit sources read `pk;export` files, pronouns.cc reads a saved API response and the
+    /// others read their export files. Private members are left out like for downloads.
+    pub fn import(&self, source: Source, data: &[u8]) -> Result<SystemVec, serde_json::Error> {
+        let system = match source {
+            #[cfg(feature = "downloader-pk")]
+            Source::PluralKit | Source::PluralKitExport => PkDownloader::new()
+                .with_privacy(self.privacy.clone())
+                .with_client(self.client())
+                .import(data),
+            #[cfg(feature = "downloader-pronouns")]
+            Source::Pronouns => PronounsDownloader::import_user_with(data, &self.pronouns_policy),
+            #[cfg(feature = "downloader-simplyplural")]
+            Source::SimplyPlural => SimplyPluralDownloader::import_export(data),
+            #[cfg(feature = "downloader-tupperbox")]
+            Source::Tupperbox => TupperboxDownloader::import_export(data),
+        }?;
+        Ok(self.skip_private(system))
+    }
+
     #[cfg(feature = "downloader-pronouns")]
-    pub async fn get_pronouns(&self, id: impl AsRef<str>) -> Result<SystemVec, reqwest::Error> {
-        let mut downloader = PronounsDownloader::new();
-        downloader.set_useragent(&self.useragent).await;
-        downloader.get(id).await
+    pub async fn get_pronouns(&self, id: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        let mut downloader = PronounsDownloader::new()
+            .with_policy(self.pronouns_policy.clone())
+            .with_client(self.client());
+        if let Some(base_url) = &self.pronouns_base_url {
+            downloader = downloader.with_base_url(base_url);
+        }
+        downloader
+            .get(id)
+            .await
+            .map(|system| self.skip_private(system))
+    }
+
+    #[cfg(feature = "downloader-simplyplural")]
+    pub async fn get_simply_plural(
+        &self,
+        url: impl AsRef<str>,
+    ) -> Result<SystemVec, DownloadError> {
+        let downloader = SimplyPluralDownloader::new().with_client(self.client());
+        downloader
+            .get_export(url)
+            .await
+            .map(|system| self.skip_private(system))
+    }
+
+    #[cfg(feature = "downloader-tupperbox")]
+    pub async fn get_tupperbox(&self, url: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        let downloader = TupperboxDownloader::new().with_client(self.client());
+        downloader
+            .get_export(url)
+            .await
+            .map(|system| self.skip_private(system))
+    }
+}
+
+pub(crate) fn build_client(useragent: &str) -> reqwest::Client {
+    let builder = reqwest::Client::builder();
+
+    #[cfg(not(target_family = "wasm"))]
+    let builder = builder.user_agent(useragent);
+
+    builder.build().unwrap()
+}
+
+/// Removes the members `policy` does not allow, reporting their names through `report`.
+pub(crate) fn skip_private(
+    system: &mut SystemVec,
+    policy: &PrivacyPolicy,
+    report: impl FnOnce(Progress<'_>),
+) {
+    let skipped = system.apply_privacy(policy);
+    if !skipped.is_empty() {
+        report(Progress::Skipped {
+            system: &system.name,
+            names: &skipped,
+        });
     }
 }
 
+/// Adds the trailing slash the endpoints are appended to.
+pub(crate) fn base_url(mut url: String) -> String {
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    url
+}
+
 pub(crate) fn transform_name(input: &str) -> String {
     // Convert the input string to bytes
     let bytes = input.as_bytes();
@@ -163,17 +519,28 @@ pub(crate) fn transform_name(input: &str) -> String {
         None => input,
     };
 
-    // Filter out non-ASCII characters and create an iterator of chars
-    let ascii_chars: String = filtered_input
-        .chars()
-        .filter(|c| {
-            c.is_ascii_alphanumeric() || c.is_ascii_punctuation() || matches!(c, ' ' | '\t')
-        })
-        .collect();
+    // Filter out control and invisible formatting characters, the badge has no glyph for them
+    let visible_chars: String = filtered_input.chars().filter(|&c| is_visible(c)).collect();
 
     // Trim leading and trailing whitespace
-    let trimmed_ascii = ascii_chars.trim();
+    let trimmed = visible_chars.trim();
 
     // Convert the trimmed string to a new String
-    String::from(trimmed_ascii)
+    String::from(trimmed)
+}
+
+/// Cleans up multi-line profile text like descriptions, returning `None` if nothing is left.
+pub(crate) fn transform_text(input: &str) -> Option<String> {
+    let text: String = input
+        .chars()
+        .map(|c| if c == '\t' { ' ' } else { c })
+        .filter(|&c| c == '\n' || is_visible(c))
+        .collect();
+    let text = text.trim();
+
+    (!text.is_empty()).then(|| String::from(text))
+}
+
+fn is_visible(c: char) -> bool {
+    !c.is_control() && !matches!(c, '\u{200b}'..='\u{200f}' | '\u{fe00}'..='\u{fe0f}')
 }
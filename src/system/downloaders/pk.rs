@@ -1,47 +1,432 @@
-use alloc::string::{String, ToString};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 
-use crate::system::{MemberStrings, SystemVec};
+use crate::system::{Birthday, GroupVec, MemberStrings, PrivacyPolicy, SystemVec};
 
-use super::transform_name;
+use super::{transform_name, transform_text, DownloadError};
 
+const BASE_URL: &str = "https://api.pluralkit.me/v2/";
+
+#[derive(Debug)]
 pub struct PkDownloader {
-    pub client: pkrs::client::PkClient,
+    base_url: String,
+    token: Option<String>,
+    privacy: PrivacyPolicy,
+    front: bool,
+    #[cfg(feature = "avatars")]
+    avatars: bool,
+    client: super::HttpClient,
 }
 
 impl PkDownloader {
     pub fn new() -> Self {
         Self {
-            client: pkrs::client::PkClient {
-                user_agent: "sysbadge downloader".to_string(),
-                ..Default::default()
-            },
+            base_url: BASE_URL.to_string(),
+            token: None,
+            privacy: PrivacyPolicy::public(),
+            front: false,
+            #[cfg(feature = "avatars")]
+            avatars: true,
+            client: super::HttpClient::new("sysbadge downloader"),
         }
     }
 
-    pub async fn get(&self, id: impl AsRef<str>) -> Result<SystemVec, reqwest::Error> {
-        let id = pkrs::model::PkId(id.as_ref().to_string());
-        let info = self.client.get_system(&id).await?;
-        let members = self.client.get_system_members(&id).await?;
+    /// Base url of the PluralKit API, for self-hosted instances.
+    pub fn with_base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = super::base_url(base_url.to_string());
+        self
+    }
 
-        let mut system = SystemVec::new(info.name.unwrap_or("no system name".to_string()));
-        system.source_id = Some(crate::system::alloc::SourceId::PluralKit(id.to_string()));
-        for member in members {
-            system.members.push(MemberStrings {
-                name: transform_name(&member.display_name.unwrap_or_else(|| member.name)),
-                pronouns: transform_name(member.pronouns.as_deref().unwrap_or("")),
-            });
+    /// Authenticates as the system with its `pk;token`.
+    ///
+    /// The API then returns private members as well, use [`crate::system::PrivacyPolicy`] to
+    /// keep them off the badge.
+    pub fn with_token(mut self, token: impl ToString) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Keeps the private members, fields and groups allowed by `policy`, others are dropped.
+    pub fn with_privacy(mut self, policy: PrivacyPolicy) -> Self {
+        self.privacy = policy;
+        self
+    }
+
+    /// Also downloads the current fronters, the badge starts on them.
+    pub fn with_front(mut self) -> Self {
+        self.front = true;
+        self
+    }
+
+    /// Leaves out the avatars, saving a request per member and 512 bytes of flash each.
+    #[cfg(feature = "avatars")]
+    pub fn without_avatars(mut self) -> Self {
+        self.avatars = false;
+        self
+    }
+
+    /// Sends requests through `client`, used to share retries and progress reporting.
+    pub fn with_client(mut self, client: super::HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    async fn get_system(&self, id: &str) -> Result<System, DownloadError> {
+        self.client
+            .get_json_authorized(
+                &format!("{}systems/{}", self.base_url, id),
+                self.token.as_deref(),
+            )
+            .await
+    }
+
+    async fn get_members(&self, id: &str) -> Result<Vec<Member>, DownloadError> {
+        self.client
+            .get_json_authorized(
+                &format!("{}systems/{}/members", self.base_url, id),
+                self.token.as_deref(),
+            )
+            .await
+    }
+
+    async fn get_groups(&self, id: &str) -> Result<Vec<Group>, DownloadError> {
+        self.client
+            .get_json_authorized(
+                &format!("{}systems/{}/groups?with_members=true", self.base_url, id),
+                self.token.as_deref(),
+            )
+            .await
+    }
+
+    /// Ids of the members in the current switch, empty without switches.
+    async fn get_fronters(&self, id: &str) -> Result<Vec<String>, DownloadError> {
+        let bytes = self
+            .client
+            .get_authorized(
+                &format!("{}systems/{}/fronters", self.base_url, id),
+                self.token.as_deref(),
+            )
+            .await?;
+        // systems that never switched get an empty response
+        if bytes.iter().all(u8::is_ascii_whitespace) {
+            return Ok(Vec::new());
         }
 
+        let fronters: Option<Fronters> = serde_json::from_slice(&bytes)?;
+        Ok(fronters
+            .map(|fronters| {
+                fronters
+                    .members
+                    .into_iter()
+                    .map(|member| member.id)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn get(&self, id: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        let id = id.as_ref();
+        let info = self.get_system(id).await?;
+        let members = self.get_members(id).await?;
+        // the group list can be private, the system is still usable without it
+        let groups = match self.get_groups(id).await {
+            Err(DownloadError::Private) => Vec::new(),
+            groups => groups?,
+        };
+
+        let front = if self.front {
+            // the front can be private, the system is still usable without it
+            match self.get_fronters(id).await {
+                Err(DownloadError::Private) => Vec::new(),
+                front => front?,
+            }
+        } else {
+            Vec::new()
+        };
+
+        let mut system = self
+            .with_avatars(Export {
+                id: id.to_string(),
+                name: info.name,
+                members,
+                groups,
+            })
+            .await;
+        system.front = front
+            .iter()
+            .filter_map(|id| {
+                system
+                    .members
+                    .iter()
+                    .position(|member| member.id.as_ref() == Some(id))
+            })
+            .map(|idx| idx as u16)
+            .collect();
         Ok(system)
     }
+
+    /// Downloads a `pk;export` file, PluralKit sends a link to it after the command.
+    pub async fn get_export(&self, url: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        let export: Export = self.client.get_json(url.as_ref()).await?;
+
+        Ok(self.with_avatars(export).await)
+    }
+
+    /// Reads a `pk;export` file like [`Self::import_export_with`] with the privacy policy of the
+    /// downloader, leaving out the private members it does not allow.
+    pub fn import(&self, data: &[u8]) -> Result<SystemVec, serde_json::Error> {
+        let export: Export = serde_json::from_slice(data)?;
+        Ok(self.into_system(export))
+    }
+
+    /// Reads the file written by PluralKit's `pk;export` command.
+    ///
+    /// Avatars are only linked in the export, they are left out to not need network access.
+    /// Private members are kept and marked, [`Self::import`] leaves them out.
+    pub fn import_export(data: &[u8]) -> Result<SystemVec, serde_json::Error> {
+        Self::import_export_with(data, &PrivacyPolicy::public())
+    }
+
+    /// Like [`Self::import_export`], keeping the private fields and groups allowed by `policy`.
+    pub fn import_export_with(
+        data: &[u8],
+        policy: &PrivacyPolicy,
+    ) -> Result<SystemVec, serde_json::Error> {
+        let export: Export = serde_json::from_slice(data)?;
+        Ok(export.into_system(policy))
+    }
+
+    /// Converts `export`, leaving out the private members the privacy policy does not allow.
+    fn into_system(&self, export: Export) -> SystemVec {
+        let mut system = export.into_system(&self.privacy);
+        super::skip_private(&mut system, &self.privacy, |progress| {
+            self.client.report(progress)
+        });
+        system
+    }
+
+    async fn with_avatars(&self, export: Export) -> SystemVec {
+        #[cfg(feature = "avatars")]
+        let avatar_urls: Vec<(String, String)> = export
+            .members
+            .iter()
+            .filter(|member| !member.hides(&self.privacy, |privacy| &privacy.avatar_privacy))
+            .filter_map(|member| Some((member.id.clone(), member.avatar_url.clone()?)))
+            .collect();
+
+        // private members are left out before their avatars are fetched
+        #[allow(unused_mut)]
+        let mut system = self.into_system(export);
+
+        #[cfg(feature = "avatars")]
+        if self.avatars {
+            let urls: Vec<Option<&str>> = system
+                .members
+                .iter()
+                .map(|member| {
+                    avatar_urls
+                        .iter()
+                        .find(|(id, _)| member.id.as_ref() == Some(id))
+                        .map(|(_, url)| url.as_str())
+                })
+                .collect();
+            let total = urls.iter().flatten().count();
+            let mut done = 0;
+            for (member, url) in system.members.iter_mut().zip(urls) {
+                if let Some(url) = url {
+                    member.avatar = super::avatar::fetch(&self.client, url).await;
+                    done += 1;
+                    self.client.report(super::Progress::Avatars { done, total });
+                }
+            }
+        }
+
+        system
+    }
 }
 
 impl super::Downloader for PkDownloader {
     async fn set_useragent(&mut self, useragent: impl ToString) {
-        self.client.user_agent = useragent.to_string();
+        self.client.set_useragent(&useragent.to_string());
     }
 
-    async fn get(&self, args: impl AsRef<str>) -> Result<SystemVec, reqwest::Error> {
+    async fn get(&self, args: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
         self.get(args).await
     }
 }
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct System {
+    pub id: String,
+    pub name: Option<String>,
+}
+
+/// Layout of a `pk;export` file, the API responses are converted through it as well.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Export {
+    pub id: String,
+    pub name: Option<String>,
+    pub members: Vec<Member>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+}
+
+impl Export {
+    fn into_system(self, policy: &PrivacyPolicy) -> SystemVec {
+        let mut system = SystemVec::new(self.name.unwrap_or("no system name".to_string()));
+        system.source_id = Some(crate::system::alloc::SourceId::PluralKit(self.id));
+
+        for group in &self.groups {
+            let private = group
+                .privacy
+                .as_ref()
+                .map_or(false, |privacy| is_private(&privacy.visibility));
+            if private && !policy.allows_id(&group.id) {
+                continue;
+            }
+            system.groups.push(GroupVec {
+                name: transform_name(group.display_name.as_deref().unwrap_or(&group.name)),
+                id: Some(group.id.clone()),
+                members: group
+                    .members
+                    .iter()
+                    .flatten()
+                    // the API lists uuids, exports list member ids
+                    .filter_map(|member| {
+                        self.members
+                            .iter()
+                            .position(|m| m.uuid == *member || m.id == *member)
+                    })
+                    .map(|idx| idx as u16)
+                    .collect(),
+            });
+        }
+
+        system.members = self
+            .members
+            .into_iter()
+            .map(|member| member.into_strings(policy))
+            .collect();
+        system
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Member {
+    pub id: String,
+    /// Missing in exports of the first version.
+    #[serde(default)]
+    pub uuid: String,
+    pub name: String,
+    pub display_name: Option<String>,
+    pub pronouns: Option<String>,
+    pub avatar_url: Option<String>,
+    pub description: Option<String>,
+    pub birthday: Option<String>,
+    pub color: Option<String>,
+    /// Only sent for requests with the token of the system, always part of exports.
+    pub privacy: Option<MemberPrivacy>,
+}
+
+impl Member {
+    /// Whether `field` of the privacy settings hides data `policy` does not allow.
+    fn hides(&self, policy: &PrivacyPolicy, field: fn(&MemberPrivacy) -> &Option<String>) -> bool {
+        self.privacy
+            .as_ref()
+            .map_or(false, |privacy| is_private(field(privacy)))
+            && !policy.allows_id(&self.id)
+    }
+
+    fn into_strings(self, policy: &PrivacyPolicy) -> MemberStrings {
+        let private = self
+            .privacy
+            .as_ref()
+            .map_or(false, |privacy| is_private(&privacy.visibility));
+        let hidden = |field: fn(&MemberPrivacy) -> &Option<String>| self.hides(policy, field);
+        let display_name = self
+            .display_name
+            .as_deref()
+            .map(transform_name)
+            .filter(|name| !name.is_empty());
+        // like PluralKit, a private name is replaced by the display name if there is one
+        let (name, display_name) = match display_name {
+            Some(display_name) if hidden(|privacy| &privacy.name_privacy) => (display_name, None),
+            display_name => (transform_name(&self.name), display_name),
+        };
+        let pronouns = if hidden(|privacy| &privacy.pronoun_privacy) {
+            ""
+        } else {
+            self.pronouns.as_deref().unwrap_or("")
+        };
+
+        MemberStrings {
+            name,
+            display_name,
+            pronouns: transform_name(pronouns),
+            pronoun_list: pronouns
+                .split(',')
+                .map(transform_name)
+                .filter(|pronouns| !pronouns.is_empty())
+                .collect(),
+            private,
+            description: self
+                .description
+                .as_deref()
+                .filter(|_| !hidden(|privacy| &privacy.description_privacy))
+                .and_then(transform_text),
+            birthday: self
+                .birthday
+                .as_deref()
+                .filter(|_| !hidden(|privacy| &privacy.birthday_privacy))
+                .and_then(Birthday::parse),
+            color: self
+                .color
+                .as_deref()
+                .and_then(|color| u32::from_str_radix(color, 16).ok()),
+            id: Some(self.id),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Group {
+    pub id: String,
+    pub name: String,
+    pub display_name: Option<String>,
+    /// Member uuids, only sent with `with_members=true`. Exports list member ids instead.
+    pub members: Option<Vec<String>>,
+    /// Only sent for requests with the token of the system, always part of exports.
+    pub privacy: Option<GroupPrivacy>,
+}
+
+/// Current switch, the members are listed like in [`Member`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Fronters {
+    pub timestamp: Option<String>,
+    pub members: Vec<Member>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MemberPrivacy {
+    pub visibility: Option<String>,
+    pub name_privacy: Option<String>,
+    pub pronoun_privacy: Option<String>,
+    pub description_privacy: Option<String>,
+    pub birthday_privacy: Option<String>,
+    pub avatar_privacy: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupPrivacy {
+    pub visibility: Option<String>,
+}
+
+fn is_private(privacy: &Option<String>) -> bool {
+    privacy.as_deref() == Some("private")
+}
@@ -1,98 +1,118 @@
-use core::cmp::Ordering;
-
 use alloc::{
+    collections::BTreeMap,
     format,
     string::{String, ToString},
+    vec,
 };
 
 const BASE_URL: &str = "https://pronouns.cc/api/";
 
+/// Which pronouns.cc entries end up on the badge, by their status.
+///
+/// Applies to pronouns, names and the entries of custom fields. Custom statuses marked as
+/// favourite count as `favourite`, others can be listed by their id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusPolicy {
+    /// Statuses kept, most preferred first, like `favourite`, `okay`, `jokingly`,
+    /// `friends_only` or `avoid`.
+    pub statuses: Vec<String>,
+    /// Pronoun sets shown together, like `she/her · they/them`. All kept sets are stored in
+    /// [`MemberStrings::pronoun_list`].
+    pub shown_pronouns: usize,
+}
+
+impl StatusPolicy {
+    /// Position of `status` in [`Self::statuses`], `None` if it is left out.
+    fn rank(&self, status: &str, custom: &BTreeMap<String, CustomPreference>) -> Option<usize> {
+        let status = match custom.get(status) {
+            Some(preference) if preference.favourite => "favourite",
+            _ => status,
+        };
+        self.statuses.iter().position(|kept| kept == status)
+    }
+}
+
+impl Default for StatusPolicy {
+    /// Favourite and okay entries, showing one pronoun set.
+    fn default() -> Self {
+        Self {
+            statuses: vec!["favourite".to_string(), "okay".to_string()],
+            shown_pronouns: 1,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PronounsDownloader {
     base_url: String,
-    client: reqwest::Client,
+    policy: StatusPolicy,
+    client: super::HttpClient,
 }
 
 impl PronounsDownloader {
     pub fn new() -> Self {
         Self {
             base_url: BASE_URL.to_string(),
-            client: Self::build_client("sysbadge downloader"),
+            policy: StatusPolicy::default(),
+            client: super::HttpClient::new("sysbadge downloader"),
         }
     }
 
-    async fn get_user(&self, id: &str) -> Result<User, reqwest::Error> {
-        let resp = self
-            .client
-            .get(&format!("{}v1/users/{}", self.base_url, id))
-            .send()
-            .await?
-            .error_for_status()?;
+    /// Base url of the pronouns.cc API, for self-hosted instances.
+    pub fn with_base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = super::base_url(base_url.to_string());
+        self
+    }
+
+    pub fn with_policy(mut self, policy: StatusPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
 
-        resp.json().await
+    /// Sends requests through `client`, used to share retries and progress reporting.
+    pub fn with_client(mut self, client: super::HttpClient) -> Self {
+        self.client = client;
+        self
     }
 
-    fn build_client(ua: &str) -> reqwest::Client {
-        let builder = reqwest::Client::builder();
+    /// Reads a user as returned by the pronouns.cc API.
+    pub fn import_user(data: &[u8]) -> Result<super::SystemVec, serde_json::Error> {
+        Self::import_user_with(data, &StatusPolicy::default())
+    }
 
-        #[cfg(not(target_family = "wasm"))]
-        let builder = builder.user_agent(ua);
+    /// Like [`Self::import_user`], keeping the entries allowed by `policy`.
+    pub fn import_user_with(
+        data: &[u8],
+        policy: &StatusPolicy,
+    ) -> Result<super::SystemVec, serde_json::Error> {
+        let user: User = serde_json::from_slice(data)?;
+        Ok(user.into_system(policy))
+    }
 
-        builder.build().unwrap()
+    async fn get_user(&self, id: &str) -> Result<User, DownloadError> {
+        self.client
+            .get_json(&format!("{}v1/users/{}", self.base_url, id))
+            .await
     }
 }
 
 impl super::Downloader for PronounsDownloader {
     async fn set_useragent(&mut self, ua: impl ToString) {
-        self.client = Self::build_client(&ua.to_string());
+        self.client.set_useragent(&ua.to_string());
     }
 
-    async fn get(&self, id: impl AsRef<str>) -> Result<super::SystemVec, reqwest::Error> {
-        let user = self.get_user(id.as_ref()).await?;
-
-        let mut system = super::SystemVec::new(transform_name(
-            &user.display_name.unwrap_or_else(|| user.name),
-        ));
-        system.source_id = Some(crate::system::alloc::SourceId::Pronouns(user.sid.clone()));
-
-        for member in user.members {
-            let mut pronouns = member.pronouns.clone();
-            pronouns.sort_by(|a, b| {
-                if a.status == b.status {
-                    return Ordering::Equal;
-                }
-                if a.status == "favourite" {
-                    return Ordering::Less;
-                } else if b.status == "favourite" {
-                    return Ordering::Greater;
-                } else {
-                    return Ordering::Equal;
-                }
-            });
-
-            system.members.push(MemberStrings {
-                name: transform_name(&member.display_name.unwrap_or_else(|| member.name)),
-                pronouns: transform_name(
-                    &pronouns
-                        .get(0)
-                        .map(|p| {
-                            p.display_text
-                                .as_deref()
-                                .unwrap_or_else(|| p.pronouns.as_str())
-                        })
-                        .unwrap_or_default(),
-                ),
-            })
-        }
-
-        Ok(system)
+    async fn get(&self, id: impl AsRef<str>) -> Result<super::SystemVec, DownloadError> {
+        Ok(self.get_user(id.as_ref()).await?.into_system(&self.policy))
     }
 }
 
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
-use crate::system::{downloaders::transform_name, MemberStrings};
+use crate::system::{
+    downloaders::{transform_name, transform_text, DownloadError},
+    MemberField, MemberStrings,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct User {
@@ -101,6 +121,96 @@ pub struct User {
     pub display_name: Option<String>,
     pub pronouns: Vec<UserPronouns>,
     pub members: Vec<UserMember>,
+    #[serde(default)]
+    pub member_list_hidden: bool,
+    /// Statuses defined by the user, by their id.
+    #[serde(default)]
+    pub custom_preferences: BTreeMap<String, CustomPreference>,
+}
+
+impl User {
+    fn into_system(self, policy: &StatusPolicy) -> super::SystemVec {
+        let mut system = super::SystemVec::new(transform_name(
+            &self.display_name.unwrap_or_else(|| self.name),
+        ));
+        system.source_id = Some(crate::system::alloc::SourceId::Pronouns(self.sid.clone()));
+
+        // entries kept by `policy`, most preferred first
+        let kept = |entries: &[FieldEntry]| -> Vec<String> {
+            let mut ranked: Vec<(usize, &str)> = entries
+                .iter()
+                .filter_map(|entry| {
+                    Some((
+                        policy.rank(&entry.status, &self.custom_preferences)?,
+                        entry.value.as_str(),
+                    ))
+                })
+                .collect();
+            // stable, entries of the same status keep their order
+            ranked.sort_by_key(|(rank, _)| *rank);
+            ranked
+                .into_iter()
+                .map(|(_, value)| transform_name(value))
+                .filter(|value| !value.is_empty())
+                .collect()
+        };
+
+        for member in self.members {
+            let pronouns: Vec<FieldEntry> = member
+                .pronouns
+                .iter()
+                .map(|pronouns| FieldEntry {
+                    value: pronouns
+                        .display_text
+                        .clone()
+                        .unwrap_or_else(|| pronouns.pronouns.clone()),
+                    status: pronouns.status.clone(),
+                })
+                .collect();
+            let pronoun_list = kept(&pronouns);
+
+            system.members.push(MemberStrings {
+                name: transform_name(&member.name),
+                display_name: member
+                    .display_name
+                    .as_deref()
+                    .map(transform_name)
+                    .filter(|name| !name.is_empty())
+                    .or_else(|| {
+                        let name = transform_name(&member.name);
+                        kept(&member.names)
+                            .into_iter()
+                            .next()
+                            .filter(|other| *other != name)
+                    }),
+                id: Some(member.sid.clone()),
+                pronouns: pronoun_list
+                    .iter()
+                    .take(policy.shown_pronouns)
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" · "),
+                pronoun_list,
+                // hidden members are only listed for their own user
+                private: member.unlisted || self.member_list_hidden,
+                description: member.bio.as_deref().and_then(transform_text),
+                fields: member
+                    .fields
+                    .iter()
+                    .filter_map(|field| {
+                        let value = kept(&field.entries).join(", ");
+                        (!value.is_empty()).then(|| MemberField {
+                            name: transform_name(&field.name),
+                            value,
+                        })
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+        }
+
+        system
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -108,7 +218,26 @@ pub struct UserMember {
     pub sid: String,
     pub name: String,
     pub display_name: Option<String>,
+    #[serde(default)]
+    pub names: Vec<FieldEntry>,
     pub pronouns: Vec<UserPronouns>,
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    #[serde(default)]
+    pub unlisted: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Field {
+    pub name: String,
+    pub entries: Vec<FieldEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FieldEntry {
+    pub value: String,
+    pub status: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -117,3 +246,10 @@ pub struct UserPronouns {
     pub display_text: Option<String>,
     pub status: String,
 }
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CustomPreference {
+    pub tooltip: String,
+    #[serde(default)]
+    pub favourite: bool,
+}
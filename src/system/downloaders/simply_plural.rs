@@ -0,0 +1,191 @@
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::system::{GroupVec, MemberField, MemberStrings, SystemVec};
+
+use super::{transform_name, transform_text, DownloadError};
+
+#[derive(Debug)]
+pub struct SimplyPluralDownloader {
+    client: super::HttpClient,
+}
+
+impl SimplyPluralDownloader {
+    pub fn new() -> Self {
+        Self {
+            client: super::HttpClient::new("sysbadge downloader"),
+        }
+    }
+
+    /// Sends requests through `client`, used to share retries and progress reporting.
+    pub fn with_client(mut self, client: super::HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Downloads an export, Simply Plural mails a link to it.
+    pub async fn get_export(&self, url: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        let export: Export = self.client.get_json(url.as_ref()).await?;
+
+        Ok(export.into_system())
+    }
+
+    /// Reads the JSON export of Simply Plural's account settings.
+    pub fn import_export(data: &[u8]) -> Result<SystemVec, serde_json::Error> {
+        let export: Export = serde_json::from_slice(data)?;
+        Ok(export.into_system())
+    }
+}
+
+impl super::Downloader for SimplyPluralDownloader {
+    async fn set_useragent(&mut self, useragent: impl ToString) {
+        self.client.set_useragent(&useragent.to_string());
+    }
+
+    async fn get(&self, url: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        self.get_export(url).await
+    }
+}
+
+use serde::{Deserialize, Serialize};
+
+/// Layout of a Simply Plural export, one list per database collection.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Export {
+    #[serde(default)]
+    pub users: Vec<User>,
+    pub members: Vec<Member>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+}
+
+impl Export {
+    fn into_system(self) -> SystemVec {
+        let user = self.users.into_iter().next();
+        let mut system = SystemVec::new(
+            user.as_ref()
+                .map(|user| transform_name(&user.username))
+                .filter(|name| !name.is_empty())
+                .unwrap_or("no system name".to_string()),
+        );
+        system.source_id = user
+            .as_ref()
+            .map(|user| crate::system::alloc::SourceId::SimplyPlural(user.id.clone()));
+
+        // custom fields are defined on the user, members only store the values
+        let mut fields: Vec<(&String, &FieldInfo)> = user
+            .as_ref()
+            .map(|user| {
+                user.fields
+                    .iter()
+                    .filter(|(_, info)| !info.private)
+                    .collect()
+            })
+            .unwrap_or_default();
+        fields.sort_by_key(|(_, info)| info.order);
+
+        let members: Vec<&Member> = self
+            .members
+            .iter()
+            .filter(|member| !member.archived)
+            .collect();
+        for member in &members {
+            system.members.push(MemberStrings {
+                name: transform_name(&member.name),
+                pronouns: transform_name(member.pronouns.as_deref().unwrap_or("")),
+                pronoun_list: member
+                    .pronouns
+                    .as_deref()
+                    .unwrap_or("")
+                    .split(',')
+                    .map(transform_name)
+                    .filter(|pronouns| !pronouns.is_empty())
+                    .collect(),
+                description: member.desc.as_deref().and_then(transform_text),
+                color: member
+                    .color
+                    .as_deref()
+                    .and_then(|color| u32::from_str_radix(color.trim_start_matches('#'), 16).ok()),
+                fields: fields
+                    .iter()
+                    .filter_map(|(id, info)| {
+                        let value = transform_name(member.info.get(*id)?);
+                        (!value.is_empty()).then(|| MemberField {
+                            name: transform_name(&info.name),
+                            value,
+                        })
+                    })
+                    .collect(),
+                id: Some(member.id.clone()),
+                private: member.private,
+                ..Default::default()
+            });
+        }
+
+        for group in &self.groups {
+            system.groups.push(GroupVec {
+                name: transform_name(&group.name),
+                id: Some(group.id.clone()),
+                members: group
+                    .members
+                    .iter()
+                    .filter_map(|id| members.iter().position(|member| member.id == *id))
+                    .map(|idx| idx as u16)
+                    .collect(),
+            });
+        }
+
+        system
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct User {
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(default)]
+    pub username: String,
+    /// Custom fields by their id.
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FieldInfo {
+    pub name: String,
+    #[serde(default)]
+    pub order: i64,
+    #[serde(default)]
+    pub private: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Member {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub name: String,
+    pub pronouns: Option<String>,
+    pub desc: Option<String>,
+    /// Colour like `#ff8800`.
+    pub color: Option<String>,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(default)]
+    pub archived: bool,
+    /// Custom field values by the id of the field.
+    #[serde(default)]
+    pub info: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Group {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub name: String,
+    /// Member ids.
+    #[serde(default)]
+    pub members: Vec<String>,
+}
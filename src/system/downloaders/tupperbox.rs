@@ -0,0 +1,128 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::system::{Birthday, GroupVec, MemberStrings, SystemVec};
+
+use super::{transform_name, transform_text, DownloadError};
+
+#[derive(Debug)]
+pub struct TupperboxDownloader {
+    client: super::HttpClient,
+}
+
+impl TupperboxDownloader {
+    pub fn new() -> Self {
+        Self {
+            client: super::HttpClient::new("sysbadge downloader"),
+        }
+    }
+
+    /// Sends requests through `client`, used to share retries and progress reporting.
+    pub fn with_client(mut self, client: super::HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Downloads a `tul!export` file, Tupperbox sends it as a Discord attachment.
+    pub async fn get_export(&self, url: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        let export: Export = self.client.get_json(url.as_ref()).await?;
+
+        Ok(export.into_system())
+    }
+
+    /// Reads the tuppers file written by Tupperbox's `tul!export` command.
+    pub fn import_export(data: &[u8]) -> Result<SystemVec, serde_json::Error> {
+        let export: Export = serde_json::from_slice(data)?;
+        Ok(export.into_system())
+    }
+}
+
+impl super::Downloader for TupperboxDownloader {
+    async fn set_useragent(&mut self, useragent: impl ToString) {
+        self.client.set_useragent(&useragent.to_string());
+    }
+
+    async fn get(&self, url: impl AsRef<str>) -> Result<SystemVec, DownloadError> {
+        self.get_export(url).await
+    }
+}
+
+use serde::{Deserialize, Serialize};
+
+/// Layout of a `tul!export` file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Export {
+    pub tuppers: Vec<Tupper>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+}
+
+impl Export {
+    fn into_system(self) -> SystemVec {
+        // exports carry no system name
+        let mut system = SystemVec::new("no system name".to_string());
+        system.source_id = self
+            .tuppers
+            .first()
+            .map(|tupper| crate::system::alloc::SourceId::Tupperbox(tupper.user_id.clone()));
+
+        for group in &self.groups {
+            system.groups.push(GroupVec {
+                name: transform_name(&group.name),
+                id: Some(group.id.to_string()),
+                members: self
+                    .tuppers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, tupper)| tupper.group_id == Some(group.id))
+                    .map(|(idx, _)| idx as u16)
+                    .collect(),
+            });
+        }
+
+        system.members = self.tuppers.into_iter().map(Tupper::into_strings).collect();
+        system
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Tupper {
+    pub id: u64,
+    pub user_id: String,
+    pub name: String,
+    /// Name shown on proxied messages instead of `name`.
+    pub nick: Option<String>,
+    pub description: Option<String>,
+    /// Timestamp like `2000-06-28T00:00:00.000Z`.
+    pub birthday: Option<String>,
+    pub group_id: Option<u64>,
+}
+
+impl Tupper {
+    fn into_strings(self) -> MemberStrings {
+        MemberStrings {
+            name: transform_name(&self.name),
+            display_name: self
+                .nick
+                .as_deref()
+                .map(transform_name)
+                .filter(|name| !name.is_empty()),
+            description: self.description.as_deref().and_then(transform_text),
+            birthday: self
+                .birthday
+                .as_deref()
+                .and_then(|birthday| birthday.get(..10))
+                .and_then(Birthday::parse),
+            id: Some(self.id.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Group {
+    pub id: u64,
+    pub name: String,
+}
@@ -0,0 +1,123 @@
+//! Header in front of the capnp message of a flashed system.
+//!
+//! Layout, all integers little endian:
+//!
+//! | offset | size | content                     |
+//! |--------|------|-----------------------------|
+//! | 0      | 4    | [`Header::MAGIC`]           |
+//! | 4      | 2    | format version              |
+//! | 6      | 2    | [`Encoding`] of the payload |
+//! | 8      | 4    | payload length in bytes     |
+//! | 12     | 4    | CRC32 of the payload        |
+//!
+//! The header is 16 bytes long, so the payload keeps the 8 byte alignment capnp requires. The
+//! payload is padded to whole words, so a following header is aligned as well. A header of
+//! zeroes ends the list of systems.
+
+use super::SystemError;
+
+/// Version of the system format, bumped on incompatible schema changes.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Serialization of the capnp message behind a [`Header`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u16)]
+pub enum Encoding {
+    /// Flat message, read in place from flash.
+    Unpacked = 0,
+    /// Capnp packed message, unpacked into RAM before reading.
+    Packed = 1,
+}
+
+impl TryFrom<u16> for Encoding {
+    type Error = SystemError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            x if x == Encoding::Unpacked as u16 => Ok(Encoding::Unpacked),
+            x if x == Encoding::Packed as u16 => Ok(Encoding::Packed),
+            _ => Err(SystemError::UnsupportedEncoding(value)),
+        }
+    }
+}
+
+/// Integrity header of a serialized system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Header {
+    pub version: u16,
+    pub encoding: Encoding,
+    pub len: u32,
+    pub crc: u32,
+}
+
+impl Header {
+    pub const MAGIC: [u8; 4] = *b"SBsy";
+    pub const LEN: usize = 16;
+
+    /// Header of `payload` in the current [`FORMAT_VERSION`].
+    pub fn for_payload(payload: &[u8], encoding: Encoding) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            encoding,
+            len: payload.len() as u32,
+            crc: crc32fast::hash(payload),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0; Self::LEN];
+        bytes[0..4].copy_from_slice(&Self::MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6..8].copy_from_slice(&(self.encoding as u16).to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.len.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.crc.to_le_bytes());
+        bytes
+    }
+
+    /// Bytes taken by the header and the padded payload.
+    pub fn stored_len(&self) -> usize {
+        Self::LEN + (self.len as usize + 7) / 8 * 8
+    }
+
+    /// Parses the header at the start of `bytes` without looking at the payload.
+    pub fn parse(bytes: &[u8]) -> Result<Self, SystemError> {
+        if bytes.len() < Self::LEN {
+            return Err(SystemError::Truncated(bytes.len() as u32));
+        }
+
+        let magic = &bytes[0..4];
+        if magic.iter().all(|&b| b == 0xff) || magic.iter().all(|&b| b == 0) {
+            return Err(SystemError::Blank);
+        }
+        if magic != Self::MAGIC {
+            return Err(SystemError::InvalidMagic);
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != FORMAT_VERSION {
+            return Err(SystemError::UnsupportedVersion(version));
+        }
+
+        Ok(Self {
+            version,
+            encoding: u16::from_le_bytes([bytes[6], bytes[7]]).try_into()?,
+            len: u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+            crc: u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        })
+    }
+
+    /// Checks the header at the start of `bytes` and returns it with the payload it describes.
+    pub fn verify(bytes: &[u8]) -> Result<(Self, &[u8]), SystemError> {
+        let header = Self::parse(bytes)?;
+        let payload = bytes[Self::LEN..]
+            .get(..header.len as usize)
+            .ok_or(SystemError::Truncated(header.len))?;
+        if crc32fast::hash(payload) != header.crc {
+            return Err(SystemError::Checksum);
+        }
+
+        Ok((header, payload))
+    }
+}
@@ -0,0 +1,181 @@
+//! Merging a fresh download into a system that was downloaded before.
+//!
+//! Members are matched by their stable [`MemberStrings::id`], falling back to the name for
+//! sources without ids. Local edits are kept in [`Overrides`], separate from the downloaded data,
+//! so a refresh can take everything else from upstream.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::{MemberStrings, SystemVec};
+
+/// Local edits applied on top of downloaded systems.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    /// Replaces the system name.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<String>,
+    /// Member ids shown first, in this order.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub order: Vec<String>,
+    /// Edits of single members by member id.
+    pub members: BTreeMap<String, MemberOverride>,
+}
+
+/// Local edits of a single member, unset fields are taken from upstream.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemberOverride {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub display_name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub pronouns: Option<String>,
+    /// Leaves the member off the badge.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "core::ops::Not::not"))]
+    pub hidden: bool,
+}
+
+impl MemberOverride {
+    fn apply(&self, member: &mut MemberStrings) {
+        if let Some(name) = &self.name {
+            member.name = name.clone();
+        }
+        if let Some(display_name) = &self.display_name {
+            member.display_name = Some(display_name.clone());
+        }
+        if let Some(pronouns) = &self.pronouns {
+            member.pronouns = pronouns.clone();
+        }
+    }
+}
+
+/// Changes between the old and the merged system, by shown member name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Old and new name of renamed members.
+    pub renamed: Vec<(String, String)>,
+}
+
+impl MergeSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty()
+    }
+}
+
+impl core::fmt::Display for MergeSummary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        for name in &self.added {
+            writeln!(f, "+ {}", name)?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- {}", name)?;
+        }
+        for (old, new) in &self.renamed {
+            writeln!(f, "~ {} -> {}", old, new)?;
+        }
+        Ok(())
+    }
+}
+
+fn key(member: &MemberStrings) -> &str {
+    member.id.as_deref().unwrap_or(&member.name)
+}
+
+impl SystemVec {
+    /// Replaces the system with `upstream` and applies `overrides`.
+    ///
+    /// Members keep the position they had, new members are added at the end and
+    /// [`Overrides::order`] comes first.
+    pub fn merge(&mut self, mut upstream: SystemVec, overrides: &Overrides) -> MergeSummary {
+        for member in &mut upstream.members {
+            if let Some(edit) = overrides.members.get(key(member)) {
+                edit.apply(member);
+            }
+        }
+        if let Some(name) = &overrides.name {
+            upstream.name = name.clone();
+        }
+
+        let rank = |member: &MemberStrings| {
+            let id = key(member);
+            if let Some(pos) = overrides.order.iter().position(|order| order == id) {
+                (0, pos)
+            } else if let Some(pos) = self.members.iter().position(|old| key(old) == id) {
+                (1, pos)
+            } else {
+                (2, 0)
+            }
+        };
+        let mut order: Vec<usize> = (0..upstream.members.len())
+            .filter(|&idx| {
+                let member = &upstream.members[idx];
+                !overrides
+                    .members
+                    .get(key(member))
+                    .map_or(false, |edit| edit.hidden)
+            })
+            .collect();
+        // stable, so new members stay in upstream order
+        order.sort_by_key(|&idx| rank(&upstream.members[idx]));
+
+        let mut new_index = alloc::vec![None; upstream.members.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = Some(new as u16);
+        }
+        let mut members: Vec<Option<MemberStrings>> = core::mem::take(&mut upstream.members)
+            .into_iter()
+            .map(Some)
+            .collect();
+        upstream.members = order
+            .iter()
+            .filter_map(|&old| members[old].take())
+            .collect();
+        for group in &mut upstream.groups {
+            group.members = group
+                .members
+                .iter()
+                .filter_map(|&member| *new_index.get(member as usize)?)
+                .collect();
+            group.members.sort_unstable();
+        }
+        upstream.front = upstream
+            .front
+            .iter()
+            .filter_map(|&member| *new_index.get(member as usize)?)
+            .collect();
+
+        let mut summary = MergeSummary::default();
+        for member in &upstream.members {
+            match self.members.iter().find(|old| key(old) == key(member)) {
+                None => summary.added.push(member.shown_name().into()),
+                Some(old) if old.shown_name() != member.shown_name() => summary
+                    .renamed
+                    .push((old.shown_name().into(), member.shown_name().into())),
+                Some(_) => {}
+            }
+        }
+        for old in &self.members {
+            if !upstream
+                .members
+                .iter()
+                .any(|member| key(member) == key(old))
+            {
+                summary.removed.push(old.shown_name().into());
+            }
+        }
+
+        *self = upstream;
+        summary
+    }
+}
@@ -3,22 +3,91 @@ mod alloc;
 
 #[cfg(feature = "downloaders")]
 pub mod downloaders;
+mod header;
+#[cfg(feature = "alloc")]
+mod merge;
+mod packed;
+#[cfg(feature = "alloc")]
+mod privacy;
+mod systems;
 
 pub mod system_capnp {
     include!(concat!(env!("OUT_DIR"), "/system/system_capnp.rs"));
 }
 
+#[cfg(all(feature = "alloc", feature = "uf2"))]
+pub use alloc::Uf2Error;
 #[cfg(feature = "updater")]
 pub use alloc::Updater;
 #[cfg(feature = "alloc")]
-pub use alloc::{MemberStrings, SystemVec};
+pub use alloc::{BitmapVec, GroupVec, MemberField, MemberStrings, SizeError, SourceId, SystemVec};
 use capnp::message::ReaderSegments;
+pub use header::{Encoding, Header, FORMAT_VERSION};
+#[cfg(feature = "alloc")]
+pub use merge::{MemberOverride, MergeSummary, Overrides};
+#[cfg(feature = "alloc")]
+pub use privacy::PrivacyPolicy;
+pub use systems::{Systems, MAX_SYSTEMS};
 
 pub use capnp;
 
 pub trait Member {
     fn name<'a>(&'a self) -> impl AsRef<str> + 'a;
     fn pronouns<'a>(&'a self) -> impl AsRef<str> + 'a;
+
+    /// Optional avatar of the member.
+    fn avatar<'a>(&'a self) -> Option<Bitmap<'a>> {
+        None
+    }
+
+    /// Longer free form text about the member.
+    fn description(&self) -> Option<&str> {
+        None
+    }
+
+    fn birthday(&self) -> Option<Birthday> {
+        None
+    }
+
+    /// Colour of the member as `0xRRGGBB`.
+    fn color(&self) -> Option<u32> {
+        None
+    }
+
+    /// Number of additional profile fields, see [`Member::field`].
+    fn field_count(&self) -> usize {
+        0
+    }
+
+    /// Name and value of an additional profile field.
+    fn field(&self, _index: usize) -> Option<(&str, &str)> {
+        None
+    }
+
+    /// Stable id of the member in its source, like the PluralKit member id.
+    fn id<'a>(&'a self) -> Option<impl AsRef<str> + 'a> {
+        None::<&str>
+    }
+
+    /// Name shown instead of [`Member::name`] if set.
+    fn display_name<'a>(&'a self) -> Option<impl AsRef<str> + 'a> {
+        None::<&str>
+    }
+
+    /// Number of pronoun sets, see [`Member::pronoun_entry`].
+    fn pronoun_count(&self) -> usize {
+        0
+    }
+
+    /// Pronoun set at `index`, [`Member::pronouns`] is the preferred one.
+    fn pronoun_entry<'a>(&'a self, _index: usize) -> Option<impl AsRef<str> + 'a> {
+        None::<&str>
+    }
+
+    /// Whether the member is private in its source.
+    fn is_private(&self) -> bool {
+        false
+    }
 }
 
 impl<M: Member> Member for &M {
@@ -29,6 +98,136 @@ impl<M: Member> Member for &M {
     fn pronouns<'a>(&'a self) -> impl AsRef<str> + 'a {
         (*self).pronouns()
     }
+
+    fn avatar<'a>(&'a self) -> Option<Bitmap<'a>> {
+        (*self).avatar()
+    }
+
+    fn description(&self) -> Option<&str> {
+        (*self).description()
+    }
+
+    fn birthday(&self) -> Option<Birthday> {
+        (*self).birthday()
+    }
+
+    fn color(&self) -> Option<u32> {
+        (*self).color()
+    }
+
+    fn field_count(&self) -> usize {
+        (*self).field_count()
+    }
+
+    fn field(&self, index: usize) -> Option<(&str, &str)> {
+        (*self).field(index)
+    }
+
+    fn id<'a>(&'a self) -> Option<impl AsRef<str> + 'a> {
+        (*self).id()
+    }
+
+    fn display_name<'a>(&'a self) -> Option<impl AsRef<str> + 'a> {
+        (*self).display_name()
+    }
+
+    fn pronoun_count(&self) -> usize {
+        (*self).pronoun_count()
+    }
+
+    fn pronoun_entry<'a>(&'a self, index: usize) -> Option<impl AsRef<str> + 'a> {
+        (*self).pronoun_entry(index)
+    }
+
+    fn is_private(&self) -> bool {
+        (*self).is_private()
+    }
+}
+
+/// Birthday of a member, the year is optional.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Birthday {
+    pub year: Option<u16>,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Birthday {
+    /// Parses an ISO 8601 date, a year of `0004` is treated as hidden like PluralKit does.
+    pub fn parse(date: &str) -> Option<Self> {
+        let mut parts = date.trim().splitn(3, '-');
+        let year: u16 = parts.next()?.parse().ok()?;
+        let month: u8 = parts.next()?.parse().ok()?;
+        let day: u8 = parts.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        Some(Self {
+            year: if year == 4 || year == 0 {
+                None
+            } else {
+                Some(year)
+            },
+            month,
+            day,
+        })
+    }
+}
+
+impl core::fmt::Display for Birthday {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+
+        let month = MONTHS[(self.month.clamp(1, 12) - 1) as usize];
+        match self.year {
+            Some(year) => write!(f, "{} {} {}", self.day, month, year),
+            None => write!(f, "{} {}", self.day, month),
+        }
+    }
+}
+
+/// Borrowed 1-bit image.
+///
+/// Rows are packed into whole bytes with the most significant bit first, set bits are drawn in
+/// the foreground colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bitmap<'a> {
+    pub width: u16,
+    pub height: u16,
+    data: &'a [u8],
+}
+
+impl<'a> Bitmap<'a> {
+    /// Returns `None` if `data` is too short for an image of the given size.
+    pub fn new(width: u16, height: u16, data: &'a [u8]) -> Option<Self> {
+        if data.len() < Self::stride(width) * height as usize {
+            return None;
+        }
+
+        Some(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// Number of bytes per row.
+    pub const fn stride(width: u16) -> usize {
+        (width as usize + 7) / 8
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn pixel(&self, x: u16, y: u16) -> bool {
+        let byte = self.data[y as usize * Self::stride(self.width) + x as usize / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
 }
 
 pub trait System {
@@ -38,9 +237,69 @@ pub trait System {
 
     /// Function to validate the system.
     ///
-    /// This returns true in the default implementation, assuming a system cannot be invalid.
+    /// This returns true if there is no [`System::error`].
     fn is_valid(&self) -> bool {
-        true
+        self.error().is_none()
+    }
+
+    /// Reason the system cannot be shown.
+    ///
+    /// This returns `None` in the default implementation, assuming a system cannot be invalid.
+    fn error(&self) -> Option<SystemError> {
+        None
+    }
+
+    /// Public profile of the system, shown as QR code.
+    fn profile_url<'a>(&'a self) -> Option<ProfileUrl<'a>> {
+        None
+    }
+
+    /// Number of member groups, like PluralKit groups.
+    fn group_count(&self) -> usize {
+        0
+    }
+
+    fn group_name<'a>(&'a self, _group: usize) -> Option<impl AsRef<str> + 'a> {
+        None::<&str>
+    }
+
+    /// Number of members in `group`, see [`System::group_member`].
+    fn group_len(&self, _group: usize) -> usize {
+        0
+    }
+
+    /// Index of the `index`th member of `group` in the members of the system.
+    fn group_member(&self, _group: usize, _index: usize) -> Option<u16> {
+        None
+    }
+
+    /// Number of members fronting when the system was downloaded, see [`System::front_member`].
+    fn front_len(&self) -> usize {
+        0
+    }
+
+    /// Index of the `index`th fronting member in the members of the system.
+    fn front_member(&self, _index: usize) -> Option<u16> {
+        None
+    }
+
+    /// Number of systems to switch between, see [`System::select_system`].
+    fn system_count(&self) -> usize {
+        1
+    }
+
+    /// Name of the system at `index`, `None` if it is invalid.
+    fn system_name<'a>(&'a self, index: usize) -> Option<impl AsRef<str> + 'a> {
+        (index == 0 && self.is_valid()).then(|| self.name())
+    }
+
+    fn selected_system(&self) -> usize {
+        0
+    }
+
+    /// Shows the system at `index` from now on, returns `false` if there is none.
+    fn select_system(&self, index: usize) -> bool {
+        index == 0
     }
 }
 
@@ -60,34 +319,260 @@ impl<S: System> System for &S {
     fn is_valid(&self) -> bool {
         (*self).is_valid()
     }
+
+    fn error(&self) -> Option<SystemError> {
+        (*self).error()
+    }
+
+    fn profile_url<'a>(&'a self) -> Option<ProfileUrl<'a>> {
+        (*self).profile_url()
+    }
+
+    fn group_count(&self) -> usize {
+        (*self).group_count()
+    }
+
+    fn group_name<'a>(&'a self, group: usize) -> Option<impl AsRef<str> + 'a> {
+        (*self).group_name(group)
+    }
+
+    fn group_len(&self, group: usize) -> usize {
+        (*self).group_len(group)
+    }
+
+    fn group_member(&self, group: usize, index: usize) -> Option<u16> {
+        (*self).group_member(group, index)
+    }
+
+    fn front_len(&self) -> usize {
+        (*self).front_len()
+    }
+
+    fn front_member(&self, index: usize) -> Option<u16> {
+        (*self).front_member(index)
+    }
+
+    fn system_count(&self) -> usize {
+        (*self).system_count()
+    }
+
+    fn system_name<'a>(&'a self, index: usize) -> Option<impl AsRef<str> + 'a> {
+        (*self).system_name(index)
+    }
+
+    fn selected_system(&self) -> usize {
+        (*self).selected_system()
+    }
+
+    fn select_system(&self, index: usize) -> bool {
+        (*self).select_system(index)
+    }
+}
+
+/// Link to the public profile of a system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileUrl<'a> {
+    /// PluralKit system id.
+    PluralKit(&'a str),
+    /// pronouns.cc short id.
+    Pronouns(&'a str),
+    /// Custom url stored with the system.
+    Custom(&'a str),
+}
+
+impl core::fmt::Display for ProfileUrl<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::PluralKit(id) => write!(f, "https://dash.pluralkit.me/profile/s/{}", id),
+            Self::Pronouns(id) => write!(f, "https://prns.cc/{}", id),
+            Self::Custom(url) => f.write_str(url),
+        }
+    }
+}
+
+/// Reasons a serialized system is rejected by [`SystemReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SystemError {
+    /// The flash holds no system, it is erased or zeroed.
+    Blank,
+    /// Data does not start with [`Header::MAGIC`].
+    InvalidMagic,
+    /// System was written in another [`FORMAT_VERSION`].
+    UnsupportedVersion(u16),
+    /// Data is shorter than the header or the payload length it announces.
+    Truncated(u32),
+    /// Payload does not match the CRC32 of the header.
+    Checksum,
+    /// Header names an [`Encoding`] unknown to this firmware.
+    UnsupportedEncoding(u16),
+    /// Packed payload ends in the middle of a word.
+    Packing,
+    /// Unpacked system does not fit into the unpack buffer.
+    UnpackBuffer,
+    /// The capnp message is malformed or exceeds the traversal limits.
+    Message,
+    /// A text field is not valid UTF-8.
+    Utf8,
+    /// A union has a variant unknown to this schema.
+    UnknownVariant(u16),
+    /// The system has no members to show.
+    NoMembers,
+    /// More members than [`MAX_MEMBERS`].
+    TooManyMembers(u32),
+    /// Avatar data is too short for its size.
+    InvalidAvatar(u32),
+    /// Birthday with a month or day out of range.
+    InvalidBirthday(u32),
+    /// Group refers to a member index past the member list.
+    InvalidGroupMember(u16),
+    /// Front refers to a member index past the member list.
+    InvalidFrontMember(u16),
+}
+
+impl core::fmt::Display for SystemError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Blank => write!(f, "no system flashed"),
+            Self::InvalidMagic => write!(f, "invalid system header"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported system format {}", v),
+            Self::Truncated(len) => write!(f, "system truncated, expected {} bytes", len),
+            Self::Checksum => write!(f, "system checksum mismatch"),
+            Self::UnsupportedEncoding(e) => write!(f, "unsupported system encoding {}", e),
+            Self::Packing => write!(f, "malformed packed system"),
+            Self::UnpackBuffer => write!(f, "unpacked system too large"),
+            Self::Message => write!(f, "malformed system message"),
+            Self::Utf8 => write!(f, "text is not valid utf-8"),
+            Self::UnknownVariant(v) => write!(f, "unknown union variant {}", v),
+            Self::NoMembers => write!(f, "system has no members"),
+            Self::TooManyMembers(n) => write!(f, "too many members: {}", n),
+            Self::InvalidAvatar(m) => write!(f, "invalid avatar of member {}", m),
+            Self::InvalidBirthday(m) => write!(f, "invalid birthday of member {}", m),
+            Self::InvalidGroupMember(i) => write!(f, "group refers to unknown member {}", i),
+            Self::InvalidFrontMember(i) => write!(f, "front refers to unknown member {}", i),
+        }
+    }
 }
 
+impl core::error::Error for SystemError {}
+
+impl From<capnp::Error> for SystemError {
+    fn from(_: capnp::Error) -> Self {
+        Self::Message
+    }
+}
+
+impl From<capnp::NotInSchema> for SystemError {
+    fn from(err: capnp::NotInSchema) -> Self {
+        Self::UnknownVariant(err.0)
+    }
+}
+
+impl From<core::str::Utf8Error> for SystemError {
+    fn from(_: core::str::Utf8Error) -> Self {
+        Self::Utf8
+    }
+}
+
+/// Size of the flash region holding the systems, has to match `fw/memory.x`.
+pub const DATA_REGION_SIZE: usize = 64 * 1024;
+/// Size of the RAM buffer packed systems are unpacked into by the firmware.
+///
+/// Packing only helps systems larger than [`DATA_REGION_SIZE`] unpacked, so the buffer has to
+/// be larger than the data region. 96K keeps the firmware at 160K of the 256K of RAM, and the
+/// exporters reject larger systems.
+pub const UNPACK_BUFFER_SIZE: usize = 96 * 1024;
+
+/// Largest number of members, member cells are indexed by `u16`.
+pub const MAX_MEMBERS: usize = u16::MAX as usize;
+
+/// [`System`] backed by a capnp message, usually the system in flash.
+///
+/// The message is validated once on construction, so the accessors never fail afterwards. They
+/// still fall back to empty values instead of panicking.
 pub struct SystemReader<S>
 where
     S: ReaderSegments,
 {
-    pub(crate) reader: capnp::message::Reader<S>,
+    pub(crate) reader: Option<capnp::message::Reader<S>>,
+    error: Option<SystemError>,
 }
 
 impl<S: ReaderSegments> SystemReader<S> {
-    pub fn reader(&self) -> capnp::Result<system_capnp::system::Reader> {
-        self.reader.get_root()
+    /// Validates the whole message and wraps it.
+    pub fn new(reader: capnp::message::Reader<S>) -> Result<Self, SystemError> {
+        validate(reader.get_root()?)?;
+
+        Ok(Self {
+            reader: Some(reader),
+            error: None,
+        })
+    }
+
+    /// Reader without a system, shown as invalid system by the badge.
+    pub const fn invalid(error: SystemError) -> Self {
+        Self {
+            reader: None,
+            error: Some(error),
+        }
+    }
+
+    /// Root of the message, `None` if the system is invalid.
+    pub fn reader(&self) -> Option<system_capnp::system::Reader<'_>> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        self.reader.as_ref()?.get_root().ok()
     }
 }
 
 impl<'a> SystemReader<capnp::serialize::NoAllocSliceSegments<'a>> {
-    pub fn from_byte_slice(slice: &mut &'a [u8]) -> capnp::Result<Self> {
-        let reader =
-            capnp::serialize::read_message_from_flat_slice_no_alloc(slice, Default::default())?;
-        Ok(Self { reader })
+    /// Verifies the [`Header`] at the start of `slice` and reads the unpacked system behind it.
+    ///
+    /// `slice` is advanced past the system.
+    pub fn from_byte_slice(slice: &mut &'a [u8]) -> Result<Self, SystemError> {
+        let mut buf: &'a mut [capnp::Word] = &mut [];
+        Self::from_byte_slice_with_buffer(slice, &mut buf)
+    }
+
+    /// Like [`SystemReader::from_byte_slice`], but unpacks [`Encoding::Packed`] systems into `buf`.
+    ///
+    /// `buf` is shortened to the part not used by the unpacked system.
+    pub fn from_byte_slice_with_buffer(
+        slice: &mut &'a [u8],
+        buf: &mut &'a mut [capnp::Word],
+    ) -> Result<Self, SystemError> {
+        let bytes: &'a [u8] = *slice;
+        let (header, payload) = Header::verify(bytes)?;
+        *slice = bytes.get(header.stored_len()..).unwrap_or_default();
+
+        let mut payload = match header.encoding {
+            Encoding::Unpacked => payload,
+            Encoding::Packed => {
+                let words = core::mem::take(buf);
+                let len = packed::unpack(payload, capnp::Word::words_to_bytes_mut(words))?;
+                let (used, rest) = words.split_at_mut(len / 8);
+                *buf = rest;
+                capnp::Word::words_to_bytes(used)
+            }
+        };
+        let reader = capnp::serialize::read_message_from_flat_slice_no_alloc(
+            &mut payload,
+            Default::default(),
+        )?;
+        Self::new(reader)
     }
 }
 
 impl SystemReader<capnp::serialize::NoAllocSliceSegments<'static>> {
-    pub unsafe fn from_linker_symbols() -> Self {
+    /// Reads the first system of the data region, packed systems are unpacked into `buf`.
+    pub unsafe fn from_linker_symbols(
+        mut buf: &'static mut [capnp::Word],
+    ) -> Result<Self, SystemError> {
         let mut bytes = unsafe { Self::flat_bytes() };
 
-        Self::from_byte_slice(&mut bytes).unwrap()
+        Self::from_byte_slice_with_buffer(&mut bytes, &mut buf)
     }
 
     unsafe fn flat_bytes() -> &'static [u8] {
@@ -109,40 +594,326 @@ impl SystemReader<capnp::serialize::NoAllocSliceSegments<'static>> {
     }
 }
 
+fn text(text: capnp::Result<capnp::text::Reader<'_>>) -> Result<&str, SystemError> {
+    Ok(text?.to_str()?)
+}
+
+/// Walks the whole system once, so later reads cannot fail.
+fn validate(system: system_capnp::system::Reader<'_>) -> Result<(), SystemError> {
+    use system_capnp::system::Which;
+
+    text(system.get_name())?;
+    match system.which()? {
+        Which::None(()) => {}
+        Which::PkHid(id) | Which::Pronouns(id) | Which::SimplyPlural(id) | Which::Tupperbox(id) => {
+            text(id)?;
+        }
+    }
+    if system.has_url() {
+        text(system.get_url())?;
+    }
+
+    let members = system.get_members()?;
+    match members.len() as usize {
+        0 => return Err(SystemError::NoMembers),
+        len if len > MAX_MEMBERS => return Err(SystemError::TooManyMembers(members.len())),
+        _ => {}
+    }
+    for (index, member) in members.iter().enumerate() {
+        validate_member(index as u32, member)?;
+    }
+
+    if system.has_groups() {
+        for group in system.get_groups()?.iter() {
+            text(group.get_name())?;
+            if group.has_id() {
+                text(group.get_id())?;
+            }
+            for member in group.get_members()?.iter() {
+                if member as u32 >= members.len() {
+                    return Err(SystemError::InvalidGroupMember(member));
+                }
+            }
+        }
+    }
+    if system.has_front() {
+        for member in system.get_front()?.iter() {
+            if member as u32 >= members.len() {
+                return Err(SystemError::InvalidFrontMember(member));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_member(
+    index: u32,
+    member: system_capnp::member::Reader<'_>,
+) -> Result<(), SystemError> {
+    text(member.get_name())?;
+    text(member.get_pronouns())?;
+
+    if member.has_avatar() {
+        let avatar = member.get_avatar()?;
+        Bitmap::new(avatar.get_width(), avatar.get_height(), avatar.get_data()?)
+            .ok_or(SystemError::InvalidAvatar(index))?;
+    }
+    if member.has_description() {
+        text(member.get_description())?;
+    }
+    if member.has_birthday() {
+        let date = member.get_birthday()?;
+        if date.get_month() > 12 || (date.get_month() != 0 && !(1..=31).contains(&date.get_day())) {
+            return Err(SystemError::InvalidBirthday(index));
+        }
+    }
+    member.get_color().which()?;
+    if member.has_fields() {
+        for field in member.get_fields()?.iter() {
+            text(field.get_name())?;
+            text(field.get_value())?;
+        }
+    }
+    if member.has_id() {
+        text(member.get_id())?;
+    }
+    if member.has_display_name() {
+        text(member.get_display_name())?;
+    }
+    if member.has_pronoun_list() {
+        for pronouns in member.get_pronoun_list()?.iter() {
+            text(pronouns)?;
+        }
+    }
+
+    Ok(())
+}
+
 impl<S: ReaderSegments> System for SystemReader<S> {
     fn name(&self) -> &str {
-        self.reader().unwrap().get_name().unwrap().to_str().unwrap()
+        self.reader()
+            .and_then(|reader| text(reader.get_name()).ok())
+            .unwrap_or_default()
     }
 
     fn member_count(&self) -> usize {
-        self.reader().unwrap().get_members().unwrap().len() as usize
+        self.reader()
+            .and_then(|reader| reader.get_members().ok())
+            .map_or(0, |members| members.len() as usize)
     }
 
     fn member<'b>(&'b self, index: usize) -> MemberReader<'b> {
         let reader = self
             .reader()
-            .unwrap()
-            .get_members()
-            .unwrap()
-            .get(index as u32);
+            .and_then(|reader| reader.get_members().ok())
+            .filter(|members| index < members.len() as usize)
+            .map(|members| members.get(index as u32));
         MemberReader { reader }
     }
+
+    fn error(&self) -> Option<SystemError> {
+        self.error
+    }
+
+    fn profile_url<'b>(&'b self) -> Option<ProfileUrl<'b>> {
+        use system_capnp::system::Which;
+
+        let reader = self.reader()?;
+        if reader.has_url() {
+            return Some(ProfileUrl::Custom(text(reader.get_url()).ok()?));
+        }
+
+        match reader.which().ok()? {
+            Which::None(()) => None,
+            Which::PkHid(hid) => Some(ProfileUrl::PluralKit(text(hid).ok()?)),
+            Which::Pronouns(id) => Some(ProfileUrl::Pronouns(text(id).ok()?)),
+            Which::SimplyPlural(_) | Which::Tupperbox(_) => None,
+        }
+    }
+
+    fn group_count(&self) -> usize {
+        match self.reader() {
+            Some(reader) if reader.has_groups() => reader
+                .get_groups()
+                .map_or(0, |groups| groups.len() as usize),
+            _ => 0,
+        }
+    }
+
+    fn group_name<'b>(&'b self, group: usize) -> Option<&'b str> {
+        text(self.group(group)?.get_name()).ok()
+    }
+
+    fn group_len(&self, group: usize) -> usize {
+        self.group(group)
+            .and_then(|group| group.get_members().ok())
+            .map_or(0, |members| members.len() as usize)
+    }
+
+    fn group_member(&self, group: usize, index: usize) -> Option<u16> {
+        let members = self.group(group)?.get_members().ok()?;
+        if index >= members.len() as usize {
+            return None;
+        }
+
+        Some(members.get(index as u32))
+    }
+
+    fn front_len(&self) -> usize {
+        match self.reader() {
+            Some(reader) if reader.has_front() => {
+                reader.get_front().map_or(0, |front| front.len() as usize)
+            }
+            _ => 0,
+        }
+    }
+
+    fn front_member(&self, index: usize) -> Option<u16> {
+        let front = self.reader()?.get_front().ok()?;
+        if index >= front.len() as usize {
+            return None;
+        }
+
+        Some(front.get(index as u32))
+    }
 }
 
+impl<S: ReaderSegments> SystemReader<S> {
+    fn group(&self, group: usize) -> Option<system_capnp::group::Reader<'_>> {
+        let groups = self.reader()?.get_groups().ok()?;
+        if group >= groups.len() as usize {
+            return None;
+        }
+
+        Some(groups.get(group as u32))
+    }
+}
+
+/// Member of a [`SystemReader`], empty if the index was out of range.
 pub struct MemberReader<'a> {
-    pub(crate) reader: system_capnp::member::Reader<'a>,
+    pub(crate) reader: Option<system_capnp::member::Reader<'a>>,
 }
 
 impl<'a> Member for MemberReader<'a> {
     fn name(&self) -> &str {
-        self.reader.get_name().unwrap().to_str().unwrap()
+        self.reader
+            .and_then(|reader| text(reader.get_name()).ok())
+            .unwrap_or_default()
     }
 
     fn pronouns(&self) -> &str {
         self.reader
-            .get_pronouns()
-            .unwrap()
-            .to_str()
+            .and_then(|reader| text(reader.get_pronouns()).ok())
             .unwrap_or_default()
     }
+
+    fn avatar<'b>(&'b self) -> Option<Bitmap<'b>> {
+        let reader = self.reader?;
+        if !reader.has_avatar() {
+            return None;
+        }
+
+        let avatar = reader.get_avatar().ok()?;
+        Bitmap::new(
+            avatar.get_width(),
+            avatar.get_height(),
+            avatar.get_data().ok()?,
+        )
+    }
+
+    fn description(&self) -> Option<&str> {
+        let reader = self.reader?;
+        if !reader.has_description() {
+            return None;
+        }
+
+        let description = text(reader.get_description()).ok()?;
+        (!description.is_empty()).then_some(description)
+    }
+
+    fn birthday(&self) -> Option<Birthday> {
+        let reader = self.reader?;
+        if !reader.has_birthday() {
+            return None;
+        }
+
+        let date = reader.get_birthday().ok()?;
+        if date.get_month() == 0 {
+            return None;
+        }
+
+        Some(Birthday {
+            year: Some(date.get_year()).filter(|&year| year != 0),
+            month: date.get_month(),
+            day: date.get_day(),
+        })
+    }
+
+    fn color(&self) -> Option<u32> {
+        match self.reader?.get_color().which().ok()? {
+            system_capnp::member::color::Which::None(()) => None,
+            system_capnp::member::color::Which::Rgb(rgb) => Some(rgb),
+        }
+    }
+
+    fn field_count(&self) -> usize {
+        match self.reader {
+            Some(reader) if reader.has_fields() => reader
+                .get_fields()
+                .map_or(0, |fields| fields.len() as usize),
+            _ => 0,
+        }
+    }
+
+    fn field(&self, index: usize) -> Option<(&str, &str)> {
+        let fields = self.reader?.get_fields().ok()?;
+        if index >= fields.len() as usize {
+            return None;
+        }
+
+        let field = fields.get(index as u32);
+        Some((text(field.get_name()).ok()?, text(field.get_value()).ok()?))
+    }
+
+    fn id(&self) -> Option<&str> {
+        let reader = self.reader?;
+        if !reader.has_id() {
+            return None;
+        }
+
+        text(reader.get_id()).ok()
+    }
+
+    fn display_name(&self) -> Option<&str> {
+        let reader = self.reader?;
+        if !reader.has_display_name() {
+            return None;
+        }
+
+        let name = text(reader.get_display_name()).ok()?;
+        (!name.is_empty()).then_some(name)
+    }
+
+    fn pronoun_count(&self) -> usize {
+        match self.reader {
+            Some(reader) if reader.has_pronoun_list() => reader
+                .get_pronoun_list()
+                .map_or(0, |list| list.len() as usize),
+            _ => 0,
+        }
+    }
+
+    fn pronoun_entry(&self, index: usize) -> Option<&str> {
+        let list = self.reader?.get_pronoun_list().ok()?;
+        if index >= list.len() as usize {
+            return None;
+        }
+
+        text(list.get(index as u32)).ok()
+    }
+
+    fn is_private(&self) -> bool {
+        self.reader.map_or(false, |reader| reader.get_private())
+    }
 }
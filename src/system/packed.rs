@@ -0,0 +1,55 @@
+//! Unpacking of capnp packed messages without an allocator.
+//!
+//! Every word starts with a tag byte, its set bits mark the non-zero bytes following it. A tag of
+//! `0x00` is followed by the number of further zero words, a tag of `0xff` by the number of
+//! words copied verbatim after the 8 bytes of the tagged word.
+
+use super::SystemError;
+
+/// Unpacks `packed` into `out`, returning the number of bytes written.
+pub fn unpack(mut packed: &[u8], out: &mut [u8]) -> Result<usize, SystemError> {
+    let mut len = 0;
+    while let Some((&tag, rest)) = packed.split_first() {
+        packed = rest;
+
+        let word = out.get_mut(len..len + 8).ok_or(SystemError::UnpackBuffer)?;
+        for (bit, byte) in word.iter_mut().enumerate() {
+            *byte = if tag & (1 << bit) != 0 {
+                let (&value, rest) = packed.split_first().ok_or(SystemError::Packing)?;
+                packed = rest;
+                value
+            } else {
+                0
+            };
+        }
+        len += 8;
+
+        match tag {
+            0x00 => {
+                let (&count, rest) = packed.split_first().ok_or(SystemError::Packing)?;
+                packed = rest;
+                let zeros = count as usize * 8;
+                out.get_mut(len..len + zeros)
+                    .ok_or(SystemError::UnpackBuffer)?
+                    .fill(0);
+                len += zeros;
+            }
+            0xff => {
+                let (&count, rest) = packed.split_first().ok_or(SystemError::Packing)?;
+                let raw = count as usize * 8;
+                if rest.len() < raw {
+                    return Err(SystemError::Packing);
+                }
+                let (words, rest) = rest.split_at(raw);
+                packed = rest;
+                out.get_mut(len..len + raw)
+                    .ok_or(SystemError::UnpackBuffer)?
+                    .copy_from_slice(words);
+                len += raw;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(len)
+}
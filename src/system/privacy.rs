@@ -0,0 +1,77 @@
+//! Keeping private members off the badge.
+//!
+//! Downloads with a token see every member of a system. The badge is shown to everyone, so only
+//! members that are public in their source, or explicitly allowed, are kept.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::{MemberStrings, SystemVec};
+
+/// Which members are kept on the badge.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrivacyPolicy {
+    /// Ids of private members and groups kept anyway, with their private fields. Empty keeps
+    /// only what is public.
+    pub allow: Vec<String>,
+}
+
+impl PrivacyPolicy {
+    /// Only public members.
+    pub fn public() -> Self {
+        Self::default()
+    }
+
+    /// Public members and the private ones with the given ids.
+    pub fn with_allowed(allow: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            allow: allow.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn allows(&self, member: &MemberStrings) -> bool {
+        !member.private || member.id.as_deref().map_or(false, |id| self.allows_id(id))
+    }
+
+    /// Whether the private member or group `id` is kept.
+    pub fn allows_id(&self, id: &str) -> bool {
+        self.allow.iter().any(|allowed| allowed == id)
+    }
+}
+
+impl SystemVec {
+    /// Removes the members `policy` does not allow, returning their shown names.
+    pub fn apply_privacy(&mut self, policy: &PrivacyPolicy) -> Vec<String> {
+        let mut skipped = Vec::new();
+        let mut new_index = Vec::with_capacity(self.members.len());
+        let mut kept: u16 = 0;
+        self.members.retain(|member| {
+            if policy.allows(member) {
+                new_index.push(Some(kept));
+                kept += 1;
+                true
+            } else {
+                new_index.push(None);
+                skipped.push(member.shown_name().into());
+                false
+            }
+        });
+
+        for group in &mut self.groups {
+            group.members = group
+                .members
+                .iter()
+                .filter_map(|&member| *new_index.get(member as usize)?)
+                .collect();
+        }
+        self.front = self
+            .front
+            .iter()
+            .filter_map(|&member| *new_index.get(member as usize)?)
+            .collect();
+
+        skipped
+    }
+}
@@ -0,0 +1,130 @@
+use core::cell::Cell;
+
+use capnp::serialize::NoAllocSliceSegments;
+use capnp::Word;
+
+use super::{Header, MemberReader, ProfileUrl, System, SystemError, SystemReader};
+
+/// Largest number of systems read from the data region.
+pub const MAX_SYSTEMS: usize = 4;
+
+/// Several systems stored back to back in the data region, one of them is shown at a time.
+pub struct Systems<'a> {
+    systems: [SystemReader<NoAllocSliceSegments<'a>>; MAX_SYSTEMS],
+    len: usize,
+    selected: Cell<usize>,
+}
+
+impl<'a> Systems<'a> {
+    /// Reads up to [`MAX_SYSTEMS`] systems, each starting with its own [`Header`].
+    ///
+    /// Reading stops at blank flash or the zeroed header written after the last system. A system
+    /// failing validation is kept so the badge can tell why, but the systems after it cannot be
+    /// found if its header is broken. Packed systems are unpacked into `buf` one after another.
+    pub fn from_byte_slice(mut bytes: &'a [u8], mut buf: &'a mut [Word]) -> Self {
+        let mut systems = core::array::from_fn(|_| SystemReader::invalid(SystemError::Blank));
+        let mut len = 0;
+        while len < MAX_SYSTEMS {
+            if len > 0
+                && matches!(
+                    Header::parse(bytes),
+                    Err(SystemError::Blank | SystemError::Truncated(_))
+                )
+            {
+                break;
+            }
+
+            let remaining = bytes.len();
+            systems[len] = SystemReader::from_byte_slice_with_buffer(&mut bytes, &mut buf)
+                .unwrap_or_else(SystemReader::invalid);
+            len += 1;
+            if bytes.len() == remaining {
+                break;
+            }
+        }
+
+        Self {
+            systems,
+            len,
+            selected: Cell::new(0),
+        }
+    }
+
+    pub unsafe fn from_linker_symbols(buf: &'static mut [Word]) -> Self {
+        Self::from_byte_slice(unsafe { SystemReader::flat_bytes() }, buf)
+    }
+
+    fn current(&self) -> &SystemReader<NoAllocSliceSegments<'a>> {
+        &self.systems[self.selected.get()]
+    }
+}
+
+impl<'a> System for Systems<'a> {
+    fn name(&self) -> &str {
+        self.current().name()
+    }
+
+    fn member_count(&self) -> usize {
+        self.current().member_count()
+    }
+
+    fn member<'b>(&'b self, index: usize) -> MemberReader<'b> {
+        self.current().member(index)
+    }
+
+    fn error(&self) -> Option<SystemError> {
+        self.current().error()
+    }
+
+    fn profile_url<'b>(&'b self) -> Option<ProfileUrl<'b>> {
+        self.current().profile_url()
+    }
+
+    fn group_count(&self) -> usize {
+        self.current().group_count()
+    }
+
+    fn group_name<'b>(&'b self, group: usize) -> Option<&'b str> {
+        self.current().group_name(group)
+    }
+
+    fn group_len(&self, group: usize) -> usize {
+        self.current().group_len(group)
+    }
+
+    fn group_member(&self, group: usize, index: usize) -> Option<u16> {
+        self.current().group_member(group, index)
+    }
+
+    fn front_len(&self) -> usize {
+        self.current().front_len()
+    }
+
+    fn front_member(&self, index: usize) -> Option<u16> {
+        self.current().front_member(index)
+    }
+
+    fn system_count(&self) -> usize {
+        self.len
+    }
+
+    fn system_name<'b>(&'b self, index: usize) -> Option<&'b str> {
+        self.systems[..self.len]
+            .get(index)
+            .filter(|system| system.is_valid())
+            .map(|system| system.name())
+    }
+
+    fn selected_system(&self) -> usize {
+        self.selected.get()
+    }
+
+    fn select_system(&self, index: usize) -> bool {
+        if index >= self.len {
+            return false;
+        }
+
+        self.selected.set(index);
+        true
+    }
+}
@@ -16,6 +16,8 @@ pub enum Request {
     UpdateDisplay,
     GetVersion,
     Reboot,
+    GetMemberId,
+    GetMemberDisplayName,
 }
 
 impl TryFrom<u8> for Request {
@@ -33,6 +35,8 @@ impl TryFrom<u8> for Request {
             x if x == (Request::UpdateDisplay as u8) => Ok(Request::UpdateDisplay),
             x if x == (Request::GetVersion as u8) => Ok(Request::GetVersion),
             x if x == (Request::Reboot as u8) => Ok(Request::Reboot),
+            x if x == (Request::GetMemberId as u8) => Ok(Request::GetMemberId),
+            x if x == (Request::GetMemberDisplayName as u8) => Ok(Request::GetMemberDisplayName),
             _ => Err(()),
         }
     }
@@ -0,0 +1,414 @@
+//! Badge states sent by the host, the badge has to reject what it cannot show.
+
+use core::convert::Infallible;
+
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use sysbadge::badge::{
+    CurrentMembers, CurrentMenu, Select, StateError, Sysbadge, DEFAULT_MEMBER_CELLS,
+    MAX_MEMBER_CELLS, STATE_MAX_LEN, STATE_VERSION,
+};
+use sysbadge::system::{GroupVec, MemberStrings, Overrides, SystemVec};
+use sysbadge::Button;
+
+const ITERATIONS: usize = 1000;
+
+/// Display dropping every pixel, the tests only look at the state.
+struct NullDisplay;
+
+impl OriginDimensions for NullDisplay {
+    fn size(&self) -> Size {
+        Size::new(sysbadge::WIDTH, sysbadge::HEIGHT)
+    }
+}
+
+impl DrawTarget for NullDisplay {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        pixels.into_iter().for_each(drop);
+        Ok(())
+    }
+}
+
+/// Xorshift generator, deterministic so failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+}
+
+fn member(name: &str) -> MemberStrings {
+    MemberStrings {
+        name: name.into(),
+        pronouns: "they/them".into(),
+        ..Default::default()
+    }
+}
+
+fn system() -> SystemVec {
+    let mut system = SystemVec::new("Test system".into());
+    system.members.push(member("Alpha"));
+    system.members.push(member("Beta"));
+    system.members.push(member("Gamma"));
+    system.groups.push(GroupVec {
+        name: "Group".into(),
+        id: None,
+        members: vec![0, 2],
+    });
+    system
+}
+
+fn badge() -> Sysbadge<NullDisplay, SystemVec> {
+    Sysbadge::new(NullDisplay, system())
+}
+
+fn members(ids: &[u16], select: Select, search: u16) -> CurrentMembers {
+    let mut members = CurrentMembers {
+        len: ids.len() as u8,
+        sel: (0, select),
+        search,
+        ..Default::default()
+    };
+    for (cell, id) in members.members.iter_mut().zip(ids) {
+        cell.id = *id;
+    }
+    members
+}
+
+#[test]
+fn rejects_unknown_members() {
+    let mut badge = badge();
+
+    assert_eq!(
+        badge.set_current(CurrentMenu::Member(members(&[0, 3], Select::Select, 0))),
+        Err(StateError::InvalidMember(3))
+    );
+    assert_eq!(
+        badge.set_current(CurrentMenu::MemberDetail {
+            members: members(&[u16::MAX], Select::Select, 0),
+            page: 0,
+        }),
+        Err(StateError::InvalidMember(u16::MAX))
+    );
+    assert_eq!(
+        badge.set_current(CurrentMenu::Member(members(&[0], Select::Search, 3))),
+        Err(StateError::InvalidSearch(3))
+    );
+    assert_eq!(
+        badge.set_current(CurrentMenu::Member(members(&[0], Select::Group, 2))),
+        Err(StateError::InvalidSearch(2))
+    );
+    assert_eq!(badge.current(), &CurrentMenu::SystemName);
+}
+
+#[test]
+fn rejects_unknown_groups() {
+    let mut badge = badge();
+    let mut members = members(&[0], Select::Edit, 0);
+    members.group = Some(1);
+    assert_eq!(
+        badge.set_current(CurrentMenu::Member(members.clone())),
+        Err(StateError::InvalidGroup(1))
+    );
+
+    members.group = Some(0);
+    assert_eq!(badge.set_current(CurrentMenu::Member(members)), Ok(()));
+    badge.press(Button::Down);
+    assert!(matches!(badge.current(), CurrentMenu::Member(members) if members.selected() == 2));
+}
+
+#[test]
+fn letter_picker_wraps_after_last_member() {
+    let mut badge = badge();
+    let state = CurrentMenu::Member(members(&[2], Select::Search, 2));
+    assert_eq!(badge.set_current(state.clone()), Ok(()));
+    assert_eq!(badge.current(), &state);
+
+    badge.press(Button::Down);
+    assert!(matches!(badge.current(), CurrentMenu::Member(members) if members.search == 0));
+    badge.draw().unwrap();
+}
+
+fn with_id(id: &str, name: &str) -> MemberStrings {
+    MemberStrings {
+        id: Some(id.into()),
+        ..member(name)
+    }
+}
+
+fn search(badge: &Sysbadge<NullDisplay, SystemVec>) -> u16 {
+    match badge.current() {
+        CurrentMenu::Member(members) if members.sel.1 == Select::Search => members.search,
+        state => panic!("not searching: {:?}", state),
+    }
+}
+
+#[test]
+fn letter_picker_follows_merged_order() {
+    let mut system = SystemVec::new("Test system".into());
+    system.members = vec![
+        with_id("b", "Beta"),
+        with_id("d", "Delta"),
+        with_id("a", "Alpha"),
+    ];
+    let mut upstream = SystemVec::new("Test system".into());
+    upstream.members = vec![
+        with_id("a", "Alpha"),
+        with_id("b", "Beta"),
+        with_id("c", "Carol"),
+        with_id("d", "Delta"),
+        with_id("e", "Ash"),
+    ];
+    let overrides = Overrides {
+        order: vec!["d".into()],
+        ..Default::default()
+    };
+    system.merge(upstream, &overrides);
+    let names: Vec<&str> = system.members.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["Delta", "Beta", "Alpha", "Carol", "Ash"]);
+
+    let mut badge = Sysbadge::new(NullDisplay, system);
+    // Ash shares the initial of Alpha, which comes first
+    assert_eq!(
+        badge.set_current(CurrentMenu::Member(members(&[4], Select::Edit, 0))),
+        Ok(())
+    );
+    badge.press(Button::A);
+    assert_eq!(search(&badge), 2);
+
+    // alphabetical by initial and wrapping, whatever the member order
+    for expected in [1, 3, 0, 2] {
+        badge.press(Button::Down);
+        assert_eq!(search(&badge), expected);
+    }
+    for expected in [0, 3, 1, 2] {
+        badge.press(Button::Up);
+        assert_eq!(search(&badge), expected);
+    }
+    badge.draw().unwrap();
+}
+
+#[test]
+fn front_fits_member_cells() {
+    let mut system = SystemVec::new("Test system".into());
+    for name in ["A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L"] {
+        system.members.push(member(name));
+    }
+    system.front = (0..12).rev().collect();
+
+    let front = CurrentMembers::front(&system, 3).unwrap();
+    assert_eq!((front.len, front.max), (3, 3));
+    assert_eq!(
+        front.members[..3]
+            .iter()
+            .map(|cell| cell.id)
+            .collect::<Vec<_>>(),
+        [11, 10, 9]
+    );
+    let front = CurrentMembers::front(&system, u8::MAX).unwrap();
+    assert_eq!((front.len, front.max), (12, MAX_MEMBER_CELLS as u8));
+    assert!(CurrentMembers::front(&system, 0).is_none());
+
+    let mut badge = Sysbadge::new(NullDisplay, system);
+    assert!(matches!(
+        badge.current(),
+        CurrentMenu::Member(members)
+            if members.len == DEFAULT_MEMBER_CELLS && members.max == DEFAULT_MEMBER_CELLS
+    ));
+    badge.draw().unwrap();
+}
+
+#[test]
+fn clamps_decoded_detail_page() {
+    let state = CurrentMenu::MemberDetail {
+        members: members(&[1], Select::Select, 0),
+        page: 0,
+    };
+    let mut buf = [0; STATE_MAX_LEN];
+    let len = state.encode(&mut buf).unwrap();
+    buf[2] = u8::MAX;
+
+    let decoded = CurrentMenu::decode(&buf[..len]).unwrap();
+    assert!(matches!(
+        decoded,
+        CurrentMenu::MemberDetail { page: u8::MAX, .. }
+    ));
+
+    let mut badge = badge();
+    assert_eq!(badge.set_current(decoded), Ok(()));
+    assert_eq!(badge.current(), &state);
+    badge.press(Button::Down);
+    assert_eq!(badge.current(), &state);
+    badge.draw().unwrap();
+}
+
+/// One state per menu, with non-default values where the menu has a payload.
+fn states() -> Vec<CurrentMenu> {
+    let mut grouped = members(&[2, 0, 1], Select::Edit, 1);
+    grouped.sel.0 = 2;
+    grouped.max = 12;
+    grouped.group = Some(0);
+
+    vec![
+        CurrentMenu::SystemName,
+        CurrentMenu::Version,
+        CurrentMenu::Member(grouped.clone()),
+        CurrentMenu::InvalidSystem,
+        CurrentMenu::Qr,
+        CurrentMenu::MemberDetail {
+            members: grouped,
+            page: 3,
+        },
+        CurrentMenu::SystemSwitcher { sel: 2 },
+    ]
+}
+
+fn encode(state: &CurrentMenu) -> Vec<u8> {
+    let mut buf = [0; STATE_MAX_LEN];
+    let len = state.encode(&mut buf).unwrap();
+    assert_eq!(len, state.encoded_len());
+    buf[..len].to_vec()
+}
+
+#[test]
+fn round_trips_every_menu() {
+    for state in states() {
+        assert_eq!(CurrentMenu::decode(&encode(&state)), Ok(state));
+    }
+}
+
+#[test]
+fn rejects_short_buffers() {
+    for state in states() {
+        let mut buf = vec![0; state.encoded_len() - 1];
+        assert_eq!(state.encode(&mut buf), Err(StateError::BufferTooSmall));
+    }
+}
+
+#[test]
+fn rejects_truncated_states() {
+    for state in states() {
+        let bytes = encode(&state);
+        for len in 0..bytes.len() {
+            assert_eq!(
+                CurrentMenu::decode(&bytes[..len]),
+                Err(StateError::Truncated),
+                "{:?} cut to {} bytes",
+                state,
+                len
+            );
+        }
+
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(CurrentMenu::decode(&longer), Err(StateError::Truncated));
+    }
+}
+
+#[test]
+fn rejects_other_versions() {
+    for state in states() {
+        let mut bytes = encode(&state);
+        for version in [0, STATE_VERSION - 1, STATE_VERSION + 1, u8::MAX] {
+            bytes[0] = version;
+            assert_eq!(
+                CurrentMenu::decode(&bytes),
+                Err(StateError::Version(version))
+            );
+        }
+    }
+}
+
+#[test]
+fn rejects_unknown_tags() {
+    for tag in [7, 0x80, u8::MAX] {
+        assert_eq!(
+            CurrentMenu::decode(&[STATE_VERSION, tag]),
+            Err(StateError::InvalidTag(tag))
+        );
+    }
+}
+
+#[test]
+fn rejects_invalid_member_payloads() {
+    let bytes = encode(&CurrentMenu::Member(members(&[0, 1], Select::Select, 0)));
+    let with = |index: usize, value: u8| {
+        let mut bytes = bytes.clone();
+        bytes[index] = value;
+        CurrentMenu::decode(&bytes)
+    };
+
+    // layout: version, tag, len, max, sel, select, search, group, ids
+    assert_eq!(with(2, 0), Err(StateError::InvalidLength(0)));
+    assert_eq!(with(2, 17), Err(StateError::InvalidLength(17)));
+    assert_eq!(with(3, 17), Err(StateError::InvalidLength(2)));
+    assert_eq!(with(4, 2), Err(StateError::InvalidSelection(2)));
+    assert_eq!(with(4, u8::MAX), Err(StateError::InvalidSelection(u8::MAX)));
+    assert_eq!(with(5, 5), Err(StateError::InvalidSelect(5)));
+    // a longer member list than sent
+    assert_eq!(with(2, 3), Err(StateError::Truncated));
+}
+
+/// Decodes `bytes` like the firmware does and plays with the accepted states.
+fn apply(badge: &mut Sysbadge<NullDisplay, SystemVec>, bytes: &[u8], rng: &mut Rng) {
+    let Ok(state) = CurrentMenu::decode(bytes) else {
+        return;
+    };
+    if badge.set_current(state).is_err() {
+        return;
+    }
+
+    badge.draw().unwrap();
+    for _ in 0..rng.below(4) {
+        badge.press(Button::try_from(rng.below(7) as u8).unwrap());
+        badge.draw().unwrap();
+    }
+}
+
+#[test]
+fn random_states_never_panic() {
+    let mut badge = badge();
+    let mut rng = Rng(0x5747_e0f5_ba0d);
+    for _ in 0..ITERATIONS {
+        let len = rng.below(STATE_MAX_LEN + 4);
+        let mut bytes: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+        // a known version and tag get the payload decoded
+        if len >= 2 && rng.below(4) != 0 {
+            bytes[0] = STATE_VERSION;
+            bytes[1] = rng.below(8) as u8;
+        }
+
+        apply(&mut badge, &bytes, &mut rng);
+    }
+}
+
+#[test]
+fn mutated_states_never_panic() {
+    let mut badge = badge();
+    let valid: Vec<Vec<u8>> = states().iter().map(encode).collect();
+    let mut rng = Rng(0x0dd5_7a7e);
+    for _ in 0..ITERATIONS {
+        let mut bytes = valid[rng.below(valid.len())].clone();
+        for _ in 0..1 + rng.below(3) {
+            // keep the version so the payload gets decoded
+            let index = 1 + rng.below(bytes.len() - 1);
+            bytes[index] = rng.next() as u8;
+        }
+
+        apply(&mut badge, &bytes, &mut rng);
+    }
+}
@@ -0,0 +1,332 @@
+//! Downloaders against a local HTTP stand-in serving recorded API responses.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use sysbadge::system::downloaders::{
+    DownloadError, GenericDownloader, PkDownloader, Progress, PronounsDownloader, RetryPolicy,
+    Source,
+};
+use sysbadge::system::{Birthday, PrivacyPolicy, SourceId, SystemVec};
+
+const PK_SYSTEM: &str = include_str!("fixtures/pk/system.json");
+const PK_MEMBERS: &str = include_str!("fixtures/pk/members.json");
+const PK_GROUPS: &str = include_str!("fixtures/pk/groups.json");
+const PK_FRONTERS: &str = include_str!("fixtures/pk/fronters.json");
+const PRONOUNS_USER: &str = include_str!("fixtures/pronouns/user.json");
+
+/// Retries without waiting long for them.
+const FAST_RETRY: RetryPolicy = RetryPolicy {
+    max_retries: 2,
+    base_delay_ms: 1,
+    max_delay_ms: 5,
+};
+
+/// Set once `flaky` failed, it answers every later request.
+static FLAKY_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Token the stand-in accepts for `privat`.
+const TOKEN: &str = "secret-token";
+
+/// Response of the stand-in for a request path, `token` is the `Authorization` header.
+fn route(path: &str, token: Option<&str>) -> (u16, &'static str) {
+    let (source, path) = path.trim_start_matches('/').split_once('/').unwrap();
+    match (source, path) {
+        ("pk", "systems/privat/members") if token == Some(TOKEN) => (200, PK_MEMBERS),
+        ("pronouns", "v1/users/flaky") if !FLAKY_FAILED.swap(true, Ordering::SeqCst) => {
+            (503, "Service Unavailable")
+        }
+        ("pronouns", "v1/users/flaky") => (200, PRONOUNS_USER),
+        ("pk", "systems/nogrp" | "systems/privat" | "systems/exmpl") => (200, PK_SYSTEM),
+        ("pk", "systems/nogrp/members" | "systems/exmpl/members") => (200, PK_MEMBERS),
+        ("pk", "systems/exmpl/groups?with_members=true") => (200, PK_GROUPS),
+        ("pk", "systems/exmpl/fronters") => (200, PK_FRONTERS),
+        ("pk", "systems/nogrp/fronters") => (403, r#"{"code": 30001}"#),
+        (
+            "pk",
+            "systems/nogrp/groups?with_members=true" | "systems/privat/groups?with_members=true",
+        ) => (403, r#"{"code": 30001}"#),
+        ("pk", "systems/privat/members") => (403, r#"{"code": 30001}"#),
+        ("pk", "systems/limit") => (429, r#"{"code": 0, "retry_after": 1000}"#),
+        ("pk", "systems/broken") => (200, "{ not json"),
+        ("pronouns", "v1/users/abcde") => (200, PRONOUNS_USER),
+        _ => (404, r#"{"code": 20001, "message": "System not found."}"#),
+    }
+}
+
+/// Serves [`route`] on a random local port until the test process exits.
+fn serve() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            // requests have no body, only the token is needed from the headers
+            let mut token = None;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("authorization") {
+                        token = Some(value.trim().to_string());
+                    }
+                }
+                line.clear();
+            }
+
+            let path = request.split(' ').nth(1).unwrap_or("/");
+            let (status, body) = route(path, token.as_deref());
+            write!(
+                stream,
+                "HTTP/1.1 {} Stand-in\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    addr
+}
+
+fn downloader(addr: SocketAddr) -> GenericDownloader {
+    // without trailing slash, the downloaders add it
+    GenericDownloader::new()
+        .with_retry(FAST_RETRY)
+        .with_pk_base_url(format!("http://{}/pk", addr))
+        .with_pronouns_base_url(format!("http://{}/pronouns", addr))
+}
+
+fn shown_names(system: &SystemVec) -> Vec<&str> {
+    system
+        .members
+        .iter()
+        .map(|member| member.shown_name())
+        .collect()
+}
+
+#[tokio::test]
+async fn downloads_pk_system() {
+    let addr = serve();
+    let mut system = downloader(addr)
+        .get(Source::PluralKit, "exmpl")
+        .await
+        .unwrap();
+
+    assert_eq!(system.name, "Example");
+    assert!(matches!(&system.source_id, Some(SourceId::PluralKit(id)) if id == "exmpl"));
+    assert_eq!(shown_names(&system), ["charlie", "Alpha", "Beta"]);
+    assert_eq!(system.groups[0].members, [0, 1]);
+
+    system.sort_members();
+    assert_eq!(shown_names(&system), ["Alpha", "Beta", "charlie"]);
+    // the group still points at charlie and Alpha
+    assert_eq!(system.groups[0].members, [0, 2]);
+
+    let alpha = &system.members[0];
+    assert_eq!(alpha.name, "Zed");
+    assert_eq!(alpha.id.as_deref(), Some("aaaaa"));
+    assert_eq!(alpha.pronoun_list, ["they/them", "it/its"]);
+    assert_eq!(alpha.description.as_deref(), Some("Hello world"));
+    assert_eq!(alpha.birthday, Birthday::parse("0004-06-28"));
+    assert_eq!(alpha.birthday.unwrap().year, None);
+    assert_eq!(alpha.color, Some(0xff8800));
+    assert!(!alpha.private);
+}
+
+#[tokio::test]
+async fn transforms_names() {
+    let addr = serve();
+    let system = PkDownloader::new()
+        .with_base_url(format!("http://{}/pk/", addr))
+        .get("exmpl")
+        .await
+        .unwrap();
+
+    // cut at three spaces
+    assert_eq!(system.name, "Example");
+    // cut at a tab, proxy tags are dropped
+    assert_eq!(system.members[1].display_name.as_deref(), Some("Alpha"));
+    // zero width and control characters have no glyph
+    assert_eq!(system.members[2].name, "Beta");
+    // names made of whitespace fall back to the name
+    assert_eq!(system.members[2].display_name, None);
+    // two spaces are kept
+    assert_eq!(system.members[1].pronouns, "they/them,  it/its");
+    assert_eq!(system.members[0].pronoun_list, ["he/him"]);
+}
+
+#[tokio::test]
+async fn picks_favourite_pronouns() {
+    let addr = serve();
+    // bravo is unlisted
+    let system = downloader(addr)
+        .with_privacy(PrivacyPolicy::with_allowed(["mbrbb"]))
+        .get(Source::Pronouns, "abcde")
+        .await
+        .unwrap();
+
+    assert_eq!(system.name, "Example User");
+    assert!(matches!(&system.source_id, Some(SourceId::Pronouns(id)) if id == "abcde"));
+    assert_eq!(shown_names(&system), ["bravo", "Alpha"]);
+
+    let bravo = &system.members[0];
+    assert_eq!(bravo.pronouns, "they/them");
+    // avoided pronouns are left out, the favourite comes first
+    assert_eq!(bravo.pronoun_list, ["they/them", "he/him/his/his/himself"]);
+    assert_eq!(bravo.fields.len(), 1);
+    assert_eq!(bravo.fields[0].name, "Likes");
+    assert_eq!(bravo.fields[0].value, "tea, cake");
+    assert!(bravo.private);
+
+    let alpha = &system.members[1];
+    assert_eq!(alpha.pronouns, "she/her/her/hers/herself");
+    assert_eq!(alpha.display_name, None);
+    assert_eq!(alpha.description.as_deref(), Some("Hi!"));
+    assert!(!alpha.private);
+
+    let system = downloader(addr)
+        .get(Source::Pronouns, "abcde")
+        .await
+        .unwrap();
+    assert_eq!(shown_names(&system), ["Alpha"]);
+
+    let pronouns = PronounsDownloader::new().with_base_url(format!("http://{}/pronouns", addr));
+    assert!(matches!(
+        sysbadge::system::downloaders::Downloader::get(&pronouns, "nobody").await,
+        Err(DownloadError::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn maps_errors() {
+    let addr = serve();
+    let downloader = downloader(addr);
+
+    let err = downloader
+        .get(Source::PluralKit, "nosys")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DownloadError::NotFound));
+    assert_eq!(err.status(), Some(404));
+    assert!(matches!(
+        downloader.get(Source::PluralKit, "privat").await,
+        Err(DownloadError::Private)
+    ));
+    assert!(matches!(
+        downloader.get(Source::PluralKit, "limit").await,
+        Err(DownloadError::RateLimited)
+    ));
+    assert!(matches!(
+        downloader.get(Source::PluralKit, "broken").await,
+        Err(DownloadError::Malformed(_))
+    ));
+
+    // private groups do not fail the download
+    let system = downloader.get(Source::PluralKit, "nogrp").await.unwrap();
+    assert_eq!(system.members.len(), 3);
+    assert!(system.groups.is_empty());
+}
+
+#[tokio::test]
+async fn unreachable_server_is_a_network_error() {
+    // bind and drop to get a port nothing listens on
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let err = downloader(addr)
+        .get(Source::PluralKit, "exmpl")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, DownloadError::Network(_)));
+    assert_eq!(err.status(), None);
+}
+
+#[tokio::test]
+async fn retries_transient_errors() {
+    let addr = serve();
+    let retries = Arc::new(Mutex::new(Vec::new()));
+    let requests = Arc::new(AtomicUsize::new(0));
+    let downloader = downloader(addr).with_progress({
+        let retries = retries.clone();
+        let requests = requests.clone();
+        move |progress: &Progress| match progress {
+            Progress::Request { .. } => {
+                requests.fetch_add(1, Ordering::SeqCst);
+            }
+            Progress::Retry { attempt, error, .. } => {
+                retries.lock().unwrap().push((*attempt, error.status()));
+            }
+            _ => {}
+        }
+    });
+
+    let system = downloader.get(Source::Pronouns, "flaky").await.unwrap();
+    assert_eq!(system.name, "Example User");
+    assert_eq!(*retries.lock().unwrap(), [(1, Some(503))]);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    // a missing system is not retried
+    retries.lock().unwrap().clear();
+    assert!(matches!(
+        downloader.get(Source::Pronouns, "nobody").await,
+        Err(DownloadError::NotFound)
+    ));
+    assert!(retries.lock().unwrap().is_empty());
+
+    // waiting longer than the policy allows fails right away
+    assert!(matches!(
+        downloader.get(Source::PluralKit, "limit").await,
+        Err(DownloadError::RateLimited)
+    ));
+    assert!(retries.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn sends_token() {
+    let addr = serve();
+    let system = downloader(addr)
+        .with_pk_token(TOKEN)
+        .get(Source::PluralKit, "privat")
+        .await
+        .unwrap();
+    assert_eq!(system.members.len(), 3);
+
+    assert!(matches!(
+        downloader(addr)
+            .with_pk_token("wrong")
+            .get(Source::PluralKit, "privat")
+            .await,
+        Err(DownloadError::Private)
+    ));
+}
+
+#[tokio::test]
+async fn downloads_front() {
+    let addr = serve();
+    let with_front = downloader(addr).with_pk_front();
+
+    let mut system = with_front.get(Source::PluralKit, "exmpl").await.unwrap();
+    // charlie and Alpha, in the order of the switch
+    assert_eq!(system.front, [0, 1]);
+    system.sort_members();
+    assert_eq!(system.front, [2, 0]);
+
+    // a private front does not fail the download
+    let system = with_front.get(Source::PluralKit, "nogrp").await.unwrap();
+    assert!(system.front.is_empty());
+
+    let system = downloader(addr)
+        .get(Source::PluralKit, "exmpl")
+        .await
+        .unwrap();
+    assert!(system.front.is_empty());
+}
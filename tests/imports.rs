@@ -0,0 +1,475 @@
+//! Conversion of export files, runs without network access.
+
+use std::sync::{Arc, Mutex};
+
+use sysbadge::system::downloaders::{
+    GenericDownloader, PkDownloader, Progress, PronounsDownloader, SimplyPluralDownloader, Source,
+    StatusPolicy, TupperboxDownloader,
+};
+use sysbadge::system::{Birthday, PrivacyPolicy, SourceId};
+
+const PK_EXPORT: &str = r#"{
+    "version": 2,
+    "id": "exmpl",
+    "uuid": "00000000-0000-0000-0000-000000000000",
+    "name": "Example System",
+    "description": null,
+    "switches": [],
+    "members": [
+        {
+            "id": "aaaaa",
+            "uuid": "00000000-0000-0000-0000-00000000000a",
+            "name": "Alpha\t| ignored",
+            "display_name": "Al",
+            "pronouns": "they/them, it/its",
+            "avatar_url": "https://example.com/alpha.png",
+            "description": "First line\nsecond line",
+            "birthday": "2000-06-28",
+            "color": "ff8800",
+            "privacy": { "visibility": "private", "name_privacy": "public" }
+        },
+        {
+            "id": "bbbbb",
+            "uuid": "00000000-0000-0000-0000-00000000000b",
+            "name": "Beta",
+            "display_name": null,
+            "pronouns": null,
+            "avatar_url": null,
+            "description": null,
+            "birthday": null,
+            "color": null,
+            "privacy": { "visibility": "public" }
+        }
+    ],
+    "groups": [
+        {
+            "id": "ggggg",
+            "name": "group",
+            "display_name": "Group",
+            "members": ["bbbbb"]
+        }
+    ]
+}"#;
+
+#[test]
+fn imports_export() {
+    let system = PkDownloader::import_export(PK_EXPORT.as_bytes()).unwrap();
+
+    assert_eq!(system.name, "Example System");
+    assert!(matches!(&system.source_id, Some(SourceId::PluralKit(id)) if id == "exmpl"));
+    assert_eq!(system.members.len(), 2);
+
+    let alpha = &system.members[0];
+    assert_eq!(alpha.name, "Alpha");
+    assert_eq!(alpha.display_name.as_deref(), Some("Al"));
+    assert_eq!(alpha.id.as_deref(), Some("aaaaa"));
+    assert_eq!(alpha.pronouns, "they/them, it/its");
+    assert_eq!(alpha.pronoun_list, ["they/them", "it/its"]);
+    assert_eq!(
+        alpha.description.as_deref(),
+        Some("First line\nsecond line")
+    );
+    assert_eq!(alpha.birthday, Birthday::parse("2000-06-28"));
+    assert_eq!(alpha.color, Some(0xff8800));
+    assert!(alpha.private);
+    assert!(alpha.avatar.is_none());
+
+    let beta = &system.members[1];
+    assert_eq!(beta.pronouns, "");
+    assert!(beta.pronoun_list.is_empty());
+    assert!(!beta.private);
+
+    assert_eq!(system.groups.len(), 1);
+    assert_eq!(system.groups[0].name, "Group");
+    assert_eq!(system.groups[0].id.as_deref(), Some("ggggg"));
+    assert_eq!(system.groups[0].members, [1]);
+}
+
+#[test]
+fn imports_through_generic_downloader() {
+    // Alpha is private in both exports
+    let downloader =
+        GenericDownloader::new().with_privacy(PrivacyPolicy::with_allowed(["aaaaa", "m1"]));
+    for source in [Source::PluralKit, Source::PluralKitExport] {
+        let system = downloader.import(source, PK_EXPORT.as_bytes()).unwrap();
+        assert_eq!(system.members.len(), 2);
+    }
+    let system = downloader
+        .import(Source::SimplyPlural, SP_EXPORT.as_bytes())
+        .unwrap();
+    assert_eq!(system.members.len(), 2);
+    let system = downloader
+        .import(Source::Tupperbox, TUPPERBOX_EXPORT.as_bytes())
+        .unwrap();
+    assert_eq!(system.members.len(), 2);
+    assert!(downloader
+        .import(Source::Tupperbox, PK_EXPORT.as_bytes())
+        .is_err());
+}
+
+#[test]
+fn rejects_invalid_exports() {
+    assert!(PkDownloader::import_export(b"").is_err());
+    assert!(PkDownloader::import_export(b"{\"id\": \"exmpl\"}").is_err());
+}
+
+const PK_PRIVATE_EXPORT: &str = r#"{
+    "id": "exmpl",
+    "name": "Example System",
+    "members": [
+        {
+            "id": "aaaaa",
+            "name": "Real Name",
+            "display_name": "Alpha",
+            "pronouns": "she/her",
+            "description": "Secret",
+            "birthday": "2000-01-01",
+            "privacy": {
+                "visibility": "public",
+                "name_privacy": "private",
+                "pronoun_privacy": "private",
+                "description_privacy": "private",
+                "birthday_privacy": "private"
+            }
+        },
+        {
+            "id": "bbbbb",
+            "name": "Beta",
+            "pronouns": "they/them",
+            "privacy": { "visibility": "private", "name_privacy": "private" }
+        },
+        {
+            "id": "ccccc",
+            "name": "Gamma",
+            "privacy": { "visibility": "private" }
+        }
+    ],
+    "groups": [
+        { "id": "ggggg", "name": "group", "members": ["ccccc", "aaaaa"] },
+        {
+            "id": "hhhhh",
+            "name": "hidden",
+            "members": ["aaaaa"],
+            "privacy": { "visibility": "private" }
+        }
+    ]
+}"#;
+
+#[test]
+fn respects_field_privacy() {
+    let system = PkDownloader::import_export(PK_PRIVATE_EXPORT.as_bytes()).unwrap();
+
+    let alpha = &system.members[0];
+    assert_eq!(alpha.name, "Alpha");
+    assert_eq!(alpha.display_name, None);
+    assert_eq!(alpha.pronouns, "");
+    assert!(alpha.pronoun_list.is_empty());
+    assert_eq!(alpha.description, None);
+    assert_eq!(alpha.birthday, None);
+    assert_eq!(system.groups.len(), 1);
+
+    // without a display name the name stays
+    let beta = &system.members[1];
+    assert_eq!(beta.name, "Beta");
+    assert_eq!(beta.pronouns, "they/them");
+}
+
+#[test]
+fn keeps_allowed_private_fields() {
+    let policy = PrivacyPolicy::with_allowed(["aaaaa", "hhhhh"]);
+    let system = PkDownloader::import_export_with(PK_PRIVATE_EXPORT.as_bytes(), &policy).unwrap();
+
+    let alpha = &system.members[0];
+    assert_eq!(alpha.name, "Real Name");
+    assert_eq!(alpha.display_name.as_deref(), Some("Alpha"));
+    assert_eq!(alpha.pronouns, "she/her");
+    assert_eq!(alpha.description.as_deref(), Some("Secret"));
+    assert_eq!(alpha.birthday, Birthday::parse("2000-01-01"));
+    assert_eq!(system.groups.len(), 2);
+    assert_eq!(system.groups[1].members, [0]);
+}
+
+#[test]
+fn skips_private_members() {
+    let mut system = PkDownloader::import_export(PK_PRIVATE_EXPORT.as_bytes()).unwrap();
+    let skipped = system.apply_privacy(&PrivacyPolicy::public());
+
+    assert_eq!(skipped, ["Beta", "Gamma"]);
+    assert_eq!(system.members.len(), 1);
+    assert_eq!(system.groups[0].members, [0]);
+
+    let mut system = PkDownloader::import_export(PK_PRIVATE_EXPORT.as_bytes()).unwrap();
+    let skipped = system.apply_privacy(&PrivacyPolicy::with_allowed(["ccccc"]));
+
+    assert_eq!(skipped, ["Beta"]);
+    assert_eq!(system.members[1].name, "Gamma");
+    assert_eq!(system.groups[0].members, [1, 0]);
+}
+
+/// Downloader collecting the names of the private members it leaves out.
+fn reporting_downloader() -> (GenericDownloader, Arc<Mutex<Vec<String>>>) {
+    let skipped = Arc::new(Mutex::new(Vec::new()));
+    let downloader = GenericDownloader::new().with_progress({
+        let skipped = skipped.clone();
+        move |progress| {
+            if let Progress::Skipped { system, names } = progress {
+                skipped
+                    .lock()
+                    .unwrap()
+                    .extend(names.iter().map(|name| format!("{}: {}", system, name)));
+            }
+        }
+    });
+    (downloader, skipped)
+}
+
+#[test]
+fn imports_leave_out_private_members() {
+    let (downloader, skipped) = reporting_downloader();
+    let system = downloader
+        .import(Source::PluralKitExport, PK_PRIVATE_EXPORT.as_bytes())
+        .unwrap();
+
+    assert_eq!(system.members.len(), 1);
+    assert_eq!(system.members[0].name, "Alpha");
+    assert_eq!(system.groups[0].members, [0]);
+    assert_eq!(
+        *skipped.lock().unwrap(),
+        ["Example System: Beta", "Example System: Gamma"]
+    );
+
+    skipped.lock().unwrap().clear();
+    let system = downloader
+        .import(Source::SimplyPlural, SP_EXPORT.as_bytes())
+        .unwrap();
+    assert_eq!(system.members.len(), 1);
+    assert_eq!(system.members[0].name, "Gamma");
+    assert_eq!(system.groups[0].members, [0]);
+    assert_eq!(*skipped.lock().unwrap(), ["SP System: Alpha"]);
+
+    // nothing to report without private members
+    skipped.lock().unwrap().clear();
+    downloader
+        .import(Source::Tupperbox, TUPPERBOX_EXPORT.as_bytes())
+        .unwrap();
+    assert!(skipped.lock().unwrap().is_empty());
+}
+
+#[test]
+fn pk_downloader_leaves_out_private_members() {
+    let system = PkDownloader::new()
+        .with_privacy(PrivacyPolicy::with_allowed(["ccccc"]))
+        .import(PK_PRIVATE_EXPORT.as_bytes())
+        .unwrap();
+
+    assert_eq!(system.members.len(), 2);
+    assert_eq!(system.members[1].name, "Gamma");
+    assert_eq!(system.groups[0].members, [1, 0]);
+}
+
+const SP_EXPORT: &str = r##"{
+    "users": [
+        {
+            "_id": "spuser",
+            "username": "SP System",
+            "fields": {
+                "f2": { "name": "Likes", "order": 2, "private": false },
+                "f1": { "name": "Role", "order": 1, "private": false },
+                "f3": { "name": "Secret", "order": 3, "private": true }
+            }
+        }
+    ],
+    "members": [
+        {
+            "_id": "m1",
+            "name": "Alpha",
+            "pronouns": "she/her",
+            "desc": "Hello",
+            "color": "#ff8800",
+            "private": true,
+            "info": { "f1": "Host", "f2": "Tea", "f3": "hidden" }
+        },
+        { "_id": "m2", "name": "Old", "archived": true },
+        { "_id": "m3", "name": "Gamma", "color": "" }
+    ],
+    "groups": [
+        { "_id": "g1", "name": "Group", "members": ["m3", "m2", "missing"] }
+    ]
+}"##;
+
+#[test]
+fn imports_simply_plural() {
+    let system = SimplyPluralDownloader::import_export(SP_EXPORT.as_bytes()).unwrap();
+
+    assert_eq!(system.name, "SP System");
+    assert!(matches!(&system.source_id, Some(SourceId::SimplyPlural(id)) if id == "spuser"));
+    // archived members are left out
+    assert_eq!(system.members.len(), 2);
+
+    let alpha = &system.members[0];
+    assert_eq!(alpha.pronouns, "she/her");
+    assert_eq!(alpha.description.as_deref(), Some("Hello"));
+    assert_eq!(alpha.color, Some(0xff8800));
+    assert!(alpha.private);
+    let fields: Vec<_> = alpha
+        .fields
+        .iter()
+        .map(|field| (field.name.as_str(), field.value.as_str()))
+        .collect();
+    assert_eq!(fields, [("Role", "Host"), ("Likes", "Tea")]);
+
+    assert_eq!(system.members[1].color, None);
+    assert_eq!(system.groups[0].members, [1]);
+}
+
+const TUPPERBOX_EXPORT: &str = r#"{
+    "tuppers": [
+        {
+            "id": 11,
+            "user_id": "123456789",
+            "name": "Alpha",
+            "brackets": ["a:", ""],
+            "avatar_url": "https://example.com/a.png",
+            "posts": 3,
+            "show_brackets": false,
+            "birthday": "2000-06-28T00:00:00.000Z",
+            "description": "Hello",
+            "tag": null,
+            "group_id": 5,
+            "nick": "Al"
+        },
+        {
+            "id": 12,
+            "user_id": "123456789",
+            "name": "Beta",
+            "brackets": ["b:", ""],
+            "avatar_url": null,
+            "birthday": null,
+            "description": null,
+            "group_id": null,
+            "nick": null
+        }
+    ],
+    "groups": [
+        { "id": 5, "user_id": "123456789", "name": "Group", "description": null, "tag": null }
+    ]
+}"#;
+
+#[test]
+fn imports_tupperbox() {
+    let system = TupperboxDownloader::import_export(TUPPERBOX_EXPORT.as_bytes()).unwrap();
+
+    assert!(matches!(&system.source_id, Some(SourceId::Tupperbox(id)) if id == "123456789"));
+    assert_eq!(system.members.len(), 2);
+
+    let alpha = &system.members[0];
+    assert_eq!(alpha.name, "Alpha");
+    assert_eq!(alpha.display_name.as_deref(), Some("Al"));
+    assert_eq!(alpha.id.as_deref(), Some("11"));
+    assert_eq!(alpha.birthday, Birthday::parse("2000-06-28"));
+    assert_eq!(alpha.description.as_deref(), Some("Hello"));
+    assert_eq!(system.members[1].birthday, None);
+
+    assert_eq!(system.groups[0].name, "Group");
+    assert_eq!(system.groups[0].id.as_deref(), Some("5"));
+    assert_eq!(system.groups[0].members, [0]);
+}
+
+#[test]
+fn keeps_source_through_the_badge_format() {
+    let mut system = TupperboxDownloader::import_export(TUPPERBOX_EXPORT.as_bytes()).unwrap();
+    system.sort_members();
+    let decoded = sysbadge::system::SystemVec::from_bin(&system.get_bin().unwrap()).unwrap();
+    assert!(matches!(&decoded.source_id, Some(SourceId::Tupperbox(id)) if id == "123456789"));
+
+    let system = SimplyPluralDownloader::import_export(SP_EXPORT.as_bytes()).unwrap();
+    let decoded = sysbadge::system::SystemVec::from_bin(&system.get_bin().unwrap()).unwrap();
+    assert!(matches!(&decoded.source_id, Some(SourceId::SimplyPlural(id)) if id == "spuser"));
+}
+
+const PRONOUNS_USER: &str = r#"{
+    "sid": "abcde",
+    "name": "example",
+    "display_name": null,
+    "pronouns": [],
+    "custom_preferences": {
+        "0a1b": { "icon": "star", "tooltip": "Love", "size": "large", "muted": false, "favourite": true },
+        "0a1c": { "icon": "dot", "tooltip": "Meh", "size": "small", "muted": true, "favourite": false }
+    },
+    "members": [
+        {
+            "sid": "mbraa",
+            "name": "alpha",
+            "display_name": null,
+            "names": [
+                { "value": "alpha", "status": "favourite" },
+                { "value": "Al", "status": "okay" },
+                { "value": "Alpaca", "status": "jokingly" }
+            ],
+            "pronouns": [
+                { "pronouns": "xe/xem/xyr/xyrs/xemself", "display_text": "xe/xem", "status": "jokingly" },
+                { "pronouns": "they/them/their/theirs/themself", "display_text": "they/them", "status": "okay" },
+                { "pronouns": "she/her/her/hers/herself", "display_text": "she/her", "status": "0a1b" },
+                { "pronouns": "it/it/its/its/itself", "display_text": "it/its", "status": "friends_only" },
+                { "pronouns": "he/him/his/his/himself", "display_text": "he/him", "status": "0a1c" }
+            ],
+            "bio": null,
+            "fields": [
+                {
+                    "name": "Likes",
+                    "entries": [
+                        { "value": "puns", "status": "jokingly" },
+                        { "value": "tea", "status": "okay" }
+                    ]
+                }
+            ]
+        }
+    ]
+}"#;
+
+#[test]
+fn applies_pronoun_statuses() {
+    let system = PronounsDownloader::import_user(PRONOUNS_USER.as_bytes()).unwrap();
+    let alpha = &system.members[0];
+    // custom statuses marked as favourite count as favourite
+    assert_eq!(alpha.pronouns, "she/her");
+    assert_eq!(alpha.pronoun_list, ["she/her", "they/them"]);
+    // the top ranked name is already shown, lower ranked ones are not used instead
+    assert_eq!(alpha.display_name, None);
+    assert_eq!(alpha.fields[0].value, "tea");
+
+    let renamed = PRONOUNS_USER.replacen(r#""name": "alpha""#, r#""name": "a""#, 1);
+    let system = PronounsDownloader::import_user(renamed.as_bytes()).unwrap();
+    assert_eq!(system.members[0].display_name.as_deref(), Some("alpha"));
+
+    let policy = StatusPolicy {
+        statuses: vec![
+            "favourite".into(),
+            "okay".into(),
+            "friends_only".into(),
+            "jokingly".into(),
+        ],
+        shown_pronouns: 3,
+    };
+    let system = PronounsDownloader::import_user_with(PRONOUNS_USER.as_bytes(), &policy).unwrap();
+    let alpha = &system.members[0];
+    assert_eq!(alpha.pronouns, "she/her · they/them · it/its");
+    assert_eq!(
+        alpha.pronoun_list,
+        ["she/her", "they/them", "it/its", "xe/xem"]
+    );
+    assert_eq!(alpha.fields[0].value, "tea, puns");
+    assert!(!alpha.private);
+
+    let hidden = PRONOUNS_USER.replacen(
+        r#""pronouns": [],"#,
+        r#""pronouns": [], "member_list_hidden": true,"#,
+        1,
+    );
+    let system = GenericDownloader::new()
+        .with_pronouns_policy(policy)
+        .import(Source::Pronouns, hidden.as_bytes())
+        .unwrap();
+    assert_eq!(system.members[0].pronoun_list.len(), 4);
+    assert!(system.members[0].private);
+}
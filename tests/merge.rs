@@ -0,0 +1,97 @@
+//! Merging downloads into an existing system.
+
+use sysbadge::system::{GroupVec, MemberOverride, MemberStrings, Overrides, SystemVec};
+
+fn member(id: &str, name: &str) -> MemberStrings {
+    MemberStrings {
+        id: Some(id.into()),
+        name: name.into(),
+        ..Default::default()
+    }
+}
+
+fn system(members: &[(&str, &str)]) -> SystemVec {
+    let mut system = SystemVec::new("System".into());
+    system.members = members.iter().map(|(id, name)| member(id, name)).collect();
+    system
+}
+
+fn names(system: &SystemVec) -> Vec<&str> {
+    system
+        .members
+        .iter()
+        .map(|member| member.shown_name())
+        .collect()
+}
+
+#[test]
+fn keeps_local_order() {
+    let mut existing = system(&[("c", "Gamma"), ("a", "Alpha"), ("b", "Beta")]);
+    let upstream = system(&[("a", "Alpha"), ("b", "Bee"), ("d", "Delta")]);
+
+    let summary = existing.merge(upstream, &Overrides::default());
+
+    assert_eq!(names(&existing), ["Alpha", "Bee", "Delta"]);
+    assert_eq!(summary.added, ["Delta"]);
+    assert_eq!(summary.removed, ["Gamma"]);
+    assert_eq!(summary.renamed, [("Beta".to_string(), "Bee".to_string())]);
+}
+
+#[test]
+fn applies_overrides() {
+    let mut existing = system(&[("a", "Al"), ("b", "Beta")]);
+    let mut upstream = system(&[
+        ("a", "Alpha"),
+        ("b", "Beta"),
+        ("c", "Gamma"),
+        ("d", "Delta"),
+    ]);
+    upstream.groups.push(GroupVec {
+        name: "Group".into(),
+        id: None,
+        members: vec![0, 2, 3],
+    });
+    upstream.front = vec![2, 3, 0];
+
+    let mut overrides = Overrides {
+        name: Some("Short".into()),
+        order: vec!["d".into()],
+        ..Default::default()
+    };
+    overrides.members.insert(
+        "a".into(),
+        MemberOverride {
+            name: Some("Al".into()),
+            pronouns: Some("they/them".into()),
+            ..Default::default()
+        },
+    );
+    overrides.members.insert(
+        "c".into(),
+        MemberOverride {
+            hidden: true,
+            ..Default::default()
+        },
+    );
+
+    let summary = existing.merge(upstream, &overrides);
+
+    assert_eq!(existing.name, "Short");
+    assert_eq!(names(&existing), ["Delta", "Al", "Beta"]);
+    assert_eq!(existing.members[1].pronouns, "they/them");
+    // the hidden member is dropped from the group as well
+    assert_eq!(existing.groups[0].members, [0, 1]);
+    assert_eq!(existing.front, [0, 1]);
+    assert_eq!(summary.added, ["Delta"]);
+    assert!(summary.removed.is_empty());
+    assert!(summary.renamed.is_empty());
+}
+
+#[test]
+fn unchanged_download_has_empty_summary() {
+    let mut existing = system(&[("a", "Alpha"), ("b", "Beta")]);
+    let summary = existing.merge(existing.clone(), &Overrides::default());
+
+    assert!(summary.is_empty());
+    assert_eq!(summary.to_string(), "no changes\n");
+}
@@ -0,0 +1,507 @@
+//! Fuzz style tests of the flash system reader, no input may make it panic.
+
+use sysbadge::system::capnp::serialize::read_message_from_flat_slice_no_alloc;
+use sysbadge::system::capnp::serialize::NoAllocSliceSegments;
+use sysbadge::system::capnp::Word;
+use sysbadge::system::{
+    Birthday, BitmapVec, Encoding, GroupVec, Header, Member, MemberField, MemberStrings,
+    SystemError, SystemReader, SystemVec, Systems, DATA_REGION_SIZE, FORMAT_VERSION,
+};
+use sysbadge::System;
+
+const ITERATIONS: usize = 2000;
+
+/// Copy of a message with the 8 byte alignment capnp expects.
+struct Aligned {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Aligned {
+    fn new(bytes: &[u8]) -> Self {
+        let mut words = vec![0u64; (bytes.len() + 7) / 8];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(8)) {
+            let mut buf = [0; 8];
+            buf[..chunk.len()].copy_from_slice(chunk);
+            *word = u64::from_ne_bytes(buf);
+        }
+
+        Self {
+            words,
+            len: bytes.len(),
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: u64 has no padding and the slice stays within the allocation
+        unsafe { core::slice::from_raw_parts(self.words.as_ptr() as *const u8, self.len) }
+    }
+
+    fn read(&self) -> Result<SystemReader<NoAllocSliceSegments<'_>>, SystemError> {
+        let mut bytes = self.bytes();
+        SystemReader::from_byte_slice(&mut bytes)
+    }
+
+    /// Reads a bare capnp message, skipping the header checks.
+    fn read_payload(&self) -> Result<SystemReader<NoAllocSliceSegments<'_>>, SystemError> {
+        let mut bytes = self.bytes();
+        SystemReader::new(read_message_from_flat_slice_no_alloc(
+            &mut bytes,
+            Default::default(),
+        )?)
+    }
+}
+
+/// Prefixes `payload` with a matching header.
+fn with_header(payload: &[u8], encoding: Encoding) -> Vec<u8> {
+    let mut bytes = Header::for_payload(payload, encoding).to_bytes().to_vec();
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// xorshift64, enough randomness for reproducible fuzzing without extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+}
+
+fn member(name: &str) -> MemberStrings {
+    MemberStrings {
+        name: name.into(),
+        pronouns: "they/them".into(),
+        ..Default::default()
+    }
+}
+
+fn system() -> SystemVec {
+    let mut avatar = BitmapVec::new(16, 16);
+    avatar.set_pixel(3, 4, true);
+
+    let mut system = SystemVec::new("Zzzz system".into());
+    system.url = Some("https://example.com/system".into());
+    system.members.push(MemberStrings {
+        avatar: Some(avatar),
+        description: Some("Line one\nline two".into()),
+        birthday: Birthday::parse("2000-06-28"),
+        color: Some(0xff8800),
+        fields: vec![MemberField {
+            name: "Likes".into(),
+            value: "Tea".into(),
+        }],
+        id: Some("abcde".into()),
+        display_name: Some("Alpha ✨".into()),
+        pronoun_list: vec!["they/them".into(), "it/its".into()],
+        private: true,
+        ..member("Alpha")
+    });
+    system.members.push(member("Beta"));
+    system.members.push(member("Gamma"));
+    system.groups.push(GroupVec {
+        name: "Group".into(),
+        id: Some("fghij".into()),
+        members: vec![0, 2],
+    });
+    system.front = vec![2, 0];
+
+    system
+}
+
+/// Reads everything the badge could ask for, including out of range indices.
+fn exercise<S: System>(system: &S) {
+    let _ = system.name().as_ref().len();
+    let _ = system.profile_url();
+    let _ = system.is_valid();
+
+    for index in 0..system.member_count() + 2 {
+        let member = system.member(index);
+        let _ = member.name().as_ref().len();
+        let _ = member.pronouns().as_ref().len();
+        if let Some(avatar) = member.avatar() {
+            for y in 0..avatar.height {
+                for x in 0..avatar.width {
+                    let _ = avatar.pixel(x, y);
+                }
+            }
+        }
+        let _ = member.description();
+        if let Some(birthday) = member.birthday() {
+            let _ = birthday.to_string();
+        }
+        let _ = member.color();
+        for field in 0..member.field_count() + 1 {
+            let _ = member.field(field);
+        }
+        let _ = member.id().map(|id| id.as_ref().len());
+        let _ = member.display_name().map(|name| name.as_ref().len());
+        for entry in 0..member.pronoun_count() + 1 {
+            let _ = member.pronoun_entry(entry).map(|p| p.as_ref().len());
+        }
+        let _ = member.is_private();
+    }
+
+    for group in 0..system.group_count() + 1 {
+        let _ = system.group_name(group).map(|name| name.as_ref().len());
+        for index in 0..system.group_len(group) + 1 {
+            let _ = system.group_member(group, index);
+        }
+    }
+    for index in 0..system.front_len() + 1 {
+        let _ = system.front_member(index);
+    }
+}
+
+fn check(bytes: &[u8]) {
+    let aligned = Aligned::new(bytes);
+    check_result(aligned.read());
+    check_result(aligned.read_payload());
+
+    // also feed the bytes to the unpacker behind a valid header
+    let packed = Aligned::new(&with_header(bytes, Encoding::Packed));
+    let mut words = Word::allocate_zeroed_vec(1024);
+    let mut buf = &mut words[..];
+    let mut slice = packed.bytes();
+    check_result(SystemReader::from_byte_slice_with_buffer(
+        &mut slice, &mut buf,
+    ));
+}
+
+fn check_result(result: Result<SystemReader<NoAllocSliceSegments<'_>>, SystemError>) {
+    match result {
+        Ok(reader) => {
+            assert!(reader.is_valid());
+            exercise(&reader);
+        }
+        Err(err) => {
+            let reader = SystemReader::<NoAllocSliceSegments<'static>>::invalid(err);
+            assert!(!reader.is_valid());
+            assert_eq!(reader.member_count(), 0);
+            exercise(&reader);
+        }
+    }
+}
+
+#[test]
+fn reads_valid_system() {
+    let system = system();
+    let aligned = Aligned::new(&system.get_bin().unwrap());
+    let reader = aligned.read().unwrap();
+
+    assert!(reader.is_valid());
+    assert_eq!(reader.name(), "Zzzz system");
+    assert_eq!(reader.member_count(), 3);
+    assert_eq!(reader.member(0).display_name(), Some("Alpha ✨"));
+    assert_eq!(reader.member(0).field(0), Some(("Likes", "Tea")));
+    assert_eq!(reader.member(3).name(), "");
+    assert_eq!(reader.group_member(0, 1), Some(2));
+    exercise(&reader);
+}
+
+#[test]
+fn rejects_invalid_utf8() {
+    let mut payload = system().get_bin().unwrap().split_off(Header::LEN);
+    let start = payload
+        .windows(4)
+        .position(|window| window == b"Zzzz")
+        .unwrap();
+    payload[start] = 0xff;
+
+    assert_eq!(
+        Aligned::new(&with_header(&payload, Encoding::Unpacked))
+            .read()
+            .err(),
+        Some(SystemError::Utf8)
+    );
+}
+
+#[test]
+fn rejects_blank_flash() {
+    assert_eq!(
+        Aligned::new(&[0xff; 64]).read().err(),
+        Some(SystemError::Blank)
+    );
+    assert_eq!(
+        Aligned::new(&[0; 64]).read().err(),
+        Some(SystemError::Blank)
+    );
+}
+
+#[test]
+fn rejects_other_format_version() {
+    let mut bytes = system().get_bin().unwrap();
+    bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+    assert_eq!(
+        Aligned::new(&bytes).read().err(),
+        Some(SystemError::UnsupportedVersion(FORMAT_VERSION + 1))
+    );
+}
+
+#[test]
+fn rejects_corrupted_payload() {
+    let mut bytes = system().get_bin().unwrap();
+    let last = bytes.len() - Header::LEN - 1;
+    bytes[last] ^= 0x01;
+    assert_eq!(
+        Aligned::new(&bytes).read().err(),
+        Some(SystemError::Checksum)
+    );
+
+    bytes.truncate(last);
+    assert!(matches!(
+        Aligned::new(&bytes).read().err(),
+        Some(SystemError::Truncated(_))
+    ));
+}
+
+#[test]
+fn ignores_trailing_flash() {
+    let mut bytes = system().get_bin().unwrap();
+    let len = bytes.len();
+    bytes.resize(len + 4096, 0xff);
+
+    let aligned = Aligned::new(&bytes);
+    let mut slice = aligned.bytes();
+    let reader = SystemReader::from_byte_slice(&mut slice).unwrap();
+    assert_eq!(reader.member_count(), 3);
+    assert_eq!(slice.len(), Header::LEN + 4096);
+}
+
+#[test]
+fn rejects_empty_system() {
+    let bytes = SystemVec::new("Empty".into()).get_bin().unwrap();
+
+    assert_eq!(
+        Aligned::new(&bytes).read().err(),
+        Some(SystemError::NoMembers)
+    );
+}
+
+#[test]
+fn rejects_unknown_group_member() {
+    let mut system = system();
+    system.groups[0].members.push(7);
+    let bytes = system.get_bin().unwrap();
+
+    assert_eq!(
+        Aligned::new(&bytes).read().err(),
+        Some(SystemError::InvalidGroupMember(7))
+    );
+}
+
+#[test]
+fn rejects_unknown_front_member() {
+    let mut system = system();
+    system.front.push(3);
+    let bytes = system.get_bin().unwrap();
+
+    assert_eq!(
+        Aligned::new(&bytes).read().err(),
+        Some(SystemError::InvalidFrontMember(3))
+    );
+}
+
+#[test]
+fn random_blobs_never_panic() {
+    let mut rng = Rng(0x5eed_0f_5ba_d6e);
+    for _ in 0..ITERATIONS {
+        let len = rng.below(512);
+        let mut bytes: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+        // a plausible single segment header gets the pointers decoded
+        if len >= 8 && rng.below(2) == 0 {
+            bytes[..4].copy_from_slice(&0u32.to_le_bytes());
+            bytes[4..8].copy_from_slice(&((len as u32 - 8) / 8).to_le_bytes());
+        }
+
+        check(&bytes);
+    }
+}
+
+#[test]
+fn mutated_systems_never_panic() {
+    let valid = system().get_bin().unwrap().split_off(Header::LEN);
+    let mut rng = Rng(0xc0ff_ee15_600d);
+    for _ in 0..ITERATIONS {
+        let mut bytes = valid.clone();
+        for _ in 0..1 + rng.below(4) {
+            let index = rng.below(bytes.len());
+            bytes[index] = rng.next() as u8;
+        }
+        if rng.below(8) == 0 {
+            bytes.truncate(rng.below(bytes.len()));
+        }
+
+        check(&bytes);
+    }
+}
+
+#[test]
+fn reads_several_systems() {
+    let mut second = system();
+    second.name = "Second".into();
+    let mut bytes = SystemVec::get_bin_multi(&[system(), second]).unwrap();
+    let len = bytes.len();
+    bytes.resize(len + 4096, 0xff);
+
+    let aligned = Aligned::new(&bytes);
+    let systems = Systems::from_byte_slice(aligned.bytes(), &mut []);
+    assert_eq!(systems.system_count(), 2);
+    assert_eq!(systems.name(), "Zzzz system");
+    assert_eq!(systems.system_name(1), Some("Second"));
+
+    assert!(systems.select_system(1));
+    assert_eq!(systems.name(), "Second");
+    assert!(!systems.select_system(2));
+    assert_eq!(systems.selected_system(), 1);
+}
+
+#[test]
+fn ignores_stale_systems() {
+    let mut bytes = SystemVec::get_bin_multi(&[system(), system(), system()]).unwrap();
+    let bin = system().get_bin().unwrap();
+    bytes[..bin.len()].copy_from_slice(&bin);
+
+    let aligned = Aligned::new(&bytes);
+    let systems = Systems::from_byte_slice(aligned.bytes(), &mut []);
+    assert_eq!(systems.system_count(), 1);
+    assert_eq!(SystemVec::from_bin_multi(&bytes).unwrap().len(), 1);
+}
+
+#[test]
+fn keeps_invalid_systems_selectable() {
+    let mut bytes = SystemVec::get_bin_multi(&[system(), system()]).unwrap();
+    let last = bytes.len() - Header::LEN - 1;
+    bytes[last] ^= 0x01;
+
+    let aligned = Aligned::new(&bytes);
+    let systems = Systems::from_byte_slice(aligned.bytes(), &mut []);
+    assert_eq!(systems.system_count(), 2);
+    assert!(systems.is_valid());
+    assert_eq!(systems.system_name(1), None);
+
+    systems.select_system(1);
+    assert_eq!(systems.error(), Some(SystemError::Checksum));
+    exercise(&systems);
+}
+
+#[test]
+fn blank_flash_is_one_invalid_system() {
+    let aligned = Aligned::new(&[0xff; 256]);
+    let systems = Systems::from_byte_slice(aligned.bytes(), &mut []);
+
+    assert_eq!(systems.system_count(), 1);
+    assert_eq!(systems.error(), Some(SystemError::Blank));
+}
+
+#[test]
+fn packs_large_systems() {
+    let mut system = system();
+    for i in 0..160 {
+        system.members.push(MemberStrings {
+            avatar: Some(BitmapVec::new(64, 64)),
+            ..member(&format!("Member {}", i))
+        });
+    }
+    let bytes = system.get_bin().unwrap();
+    assert!(bytes.len() < DATA_REGION_SIZE);
+    assert_eq!(Header::parse(&bytes).unwrap().encoding, Encoding::Packed);
+
+    let aligned = Aligned::new(&bytes);
+    assert_eq!(aligned.read().err(), Some(SystemError::UnpackBuffer));
+
+    let mut words = Word::allocate_zeroed_vec(system.members.len() * 128);
+    let systems = Systems::from_byte_slice(aligned.bytes(), &mut words);
+    assert!(systems.is_valid());
+    assert_eq!(systems.member_count(), system.members.len());
+    assert_eq!(systems.member(0).display_name(), Some("Alpha ✨"));
+    exercise(&systems);
+}
+
+#[test]
+fn reports_systems_over_budget() {
+    let mut system = system();
+    for i in 0..100 {
+        let description = (0..1000)
+            .map(|c| char::from(b'a' + ((c * 7 + i) % 26) as u8))
+            .collect();
+        system.members.push(MemberStrings {
+            description: Some(description),
+            ..member(&format!("Member {}", i))
+        });
+    }
+
+    let err = system.get_bin().unwrap_err();
+    assert_eq!(err.budget, DATA_REGION_SIZE);
+    assert!(err.size > DATA_REGION_SIZE);
+    assert_eq!(err.largest_members.len(), 5);
+    assert!(err
+        .to_string()
+        .starts_with(&format!("system is {} bytes, budget is 64K", err.size)));
+}
+
+#[test]
+fn decodes_written_systems() {
+    let mut second = system();
+    second.name = "Second".into();
+    second.members.truncate(1);
+    second.front = vec![0];
+    let systems = [system(), second];
+
+    let bin = SystemVec::get_bin_multi(&systems).unwrap();
+    let decoded = SystemVec::from_bin_multi(&bin).unwrap();
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded[1].name, "Second");
+
+    let decoded = &decoded[0];
+    let original = &systems[0];
+    assert_eq!(decoded.name, original.name);
+    assert_eq!(decoded.url, original.url);
+    assert_eq!(decoded.members.len(), original.members.len());
+    let (alpha, expected) = (&decoded.members[0], &original.members[0]);
+    assert_eq!(alpha.name, expected.name);
+    assert_eq!(alpha.display_name, expected.display_name);
+    assert_eq!(alpha.description, expected.description);
+    assert_eq!(alpha.birthday, expected.birthday);
+    assert_eq!(alpha.color, expected.color);
+    assert_eq!(alpha.fields, expected.fields);
+    assert_eq!(alpha.pronoun_list, expected.pronoun_list);
+    assert_eq!(alpha.avatar, expected.avatar);
+    assert!(alpha.private);
+    assert_eq!(decoded.groups[0].id, original.groups[0].id);
+    assert_eq!(decoded.groups[0].members, original.groups[0].members);
+    assert_eq!(decoded.front, original.front);
+
+    let uf2 = SystemVec::get_uf2_multi(&systems, 0x1010_0000).unwrap();
+    let decoded = SystemVec::from_uf2(&uf2, 0x1010_0000).unwrap();
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded[0].members[0].id, original.members[0].id);
+}
+
+#[test]
+fn rejects_broken_images() {
+    let bin = system().get_bin().unwrap();
+    assert_eq!(
+        SystemVec::from_bin(&[0xff; 64]).err(),
+        Some(SystemError::Blank)
+    );
+
+    let mut corrupted = bin.clone();
+    corrupted[Header::LEN + 20] ^= 0x55;
+    assert_eq!(
+        SystemVec::from_bin(&corrupted).err(),
+        Some(SystemError::Checksum)
+    );
+
+    let uf2 = system().get_uf2(0x1010_0000).unwrap();
+    assert!(SystemVec::from_uf2(&uf2[..uf2.len() - 1], 0x1010_0000).is_err());
+    assert!(SystemVec::from_uf2(&uf2, 0x2000_0000).is_err());
+}
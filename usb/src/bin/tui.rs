@@ -243,13 +243,13 @@ impl<U: UsbContext> App<U> {
                     }
                 }
             }
-            KeyCode::Char(' ') | KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right if let Current::Show { ref mut state } = &mut self.current => {
-                let member_count = self.badge.member_count().unwrap_or(0) as usize;
+            KeyCode::Char(' ') | KeyCode::Char('a') | KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right if let Current::Show { ref mut state } = &mut self.current => {
                 match key.code {
-                    KeyCode::Char(' ') => state.change(Button::B, member_count),
-                    KeyCode::Up => state.change(Button::Up, member_count),
-                    KeyCode::Down => state.change(Button::Down, member_count),
-                    KeyCode::Left | KeyCode::Right => state.change(Button::C, member_count),
+                    KeyCode::Char(' ') => state.change(Button::B, &self.badge),
+                    KeyCode::Char('a') => state.change(Button::A, &self.badge),
+                    KeyCode::Up => state.change(Button::Up, &self.badge),
+                    KeyCode::Down => state.change(Button::Down, &self.badge),
+                    KeyCode::Left | KeyCode::Right => state.change(Button::C, &self.badge),
                     _ => {}
                 }
             }
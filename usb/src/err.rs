@@ -4,6 +4,7 @@ pub enum Error {
     Utf8(std::string::FromUtf8Error),
     NoDevice,
     Io(std::io::Error),
+    State(sysbadge::badge::StateError),
 }
 
 impl From<rusb::Error> for Error {
@@ -24,6 +25,12 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<sysbadge::badge::StateError> for Error {
+    fn from(err: sysbadge::badge::StateError) -> Self {
+        Self::State(err)
+    }
+}
+
 impl core::fmt::Display for Error {
     fn fmt(&self, F: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -31,6 +38,7 @@ impl core::fmt::Display for Error {
             Self::Utf8(err) => write!(F, "UTF-8 error: {}", err),
             Self::NoDevice => write!(F, "No device found"),
             Self::Io(err) => write!(F, "I/O error: {}", err),
+            Self::State(err) => write!(F, "State error: {}", err),
         }
     }
 }
@@ -42,6 +50,7 @@ impl std::error::Error for Error {
             Self::Utf8(err) => Some(err),
             Self::NoDevice => None,
             Self::Io(err) => Some(err),
+            Self::State(err) => Some(err),
         }
     }
 }
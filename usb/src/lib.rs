@@ -13,9 +13,10 @@ pub use rusb;
 pub mod err;
 
 pub use err::{Error, Result};
+use sysbadge::badge::{CurrentMenu, STATE_MAX_LEN};
 use sysbadge::system::Member;
 use sysbadge::usb::{BootSel, VersionType};
-use sysbadge::{badge::CurrentMenu, System};
+use sysbadge::System;
 
 pub const VID: u16 = sysbadge::usb::VID;
 pub const PID: u16 = sysbadge::usb::PID;
@@ -126,9 +127,41 @@ impl<T: UsbContext> UsbSysbadge<T> {
         Ok(String::from_utf8((&buf[..n]).to_vec())?)
     }
 
+    pub fn member_id(&self, index: u16) -> Result<String> {
+        let mut buf = [0; 64];
+        let n = self.handle.read_control(
+            constants::LIBUSB_ENDPOINT_IN
+                | constants::LIBUSB_REQUEST_TYPE_VENDOR
+                | constants::LIBUSB_RECIPIENT_INTERFACE,
+            sysbadge::usb::Request::GetMemberId as u8,
+            index,
+            0,
+            &mut buf,
+            self.timeout,
+        )?;
+
+        Ok(String::from_utf8((&buf[..n]).to_vec())?)
+    }
+
+    pub fn member_display_name(&self, index: u16) -> Result<String> {
+        let mut buf = [0; 64];
+        let n = self.handle.read_control(
+            constants::LIBUSB_ENDPOINT_IN
+                | constants::LIBUSB_REQUEST_TYPE_VENDOR
+                | constants::LIBUSB_RECIPIENT_INTERFACE,
+            sysbadge::usb::Request::GetMemberDisplayName as u8,
+            index,
+            0,
+            &mut buf,
+            self.timeout,
+        )?;
+
+        Ok(String::from_utf8((&buf[..n]).to_vec())?)
+    }
+
     pub fn get_state(&self) -> Result<CurrentMenu> {
-        let mut buf = [0; core::mem::size_of::<CurrentMenu>()];
-        self.handle.read_control(
+        let mut buf = [0; STATE_MAX_LEN];
+        let n = self.handle.read_control(
             constants::LIBUSB_ENDPOINT_IN
                 | constants::LIBUSB_REQUEST_TYPE_VENDOR
                 | constants::LIBUSB_RECIPIENT_INTERFACE,
@@ -139,11 +172,12 @@ impl<T: UsbContext> UsbSysbadge<T> {
             self.timeout,
         )?;
 
-        Ok(CurrentMenu::from_bytes(&buf))
+        Ok(CurrentMenu::decode(&buf[..n])?)
     }
 
     pub fn set_state(&self, state: &CurrentMenu) -> Result {
-        let buf = state.as_bytes();
+        let mut buf = [0; STATE_MAX_LEN];
+        let n = state.encode(&mut buf)?;
         self.handle.write_control(
             constants::LIBUSB_ENDPOINT_OUT
                 | constants::LIBUSB_REQUEST_TYPE_VENDOR
@@ -151,7 +185,7 @@ impl<T: UsbContext> UsbSysbadge<T> {
             sysbadge::usb::Request::SetState as u8,
             0,
             0,
-            &buf,
+            &buf[..n],
             self.timeout,
         )?;
 
@@ -281,4 +315,18 @@ impl<'u, T: UsbContext + 'u> Member for UsbMember<'u, T> {
             .member_pronouns(self.id as u16)
             .unwrap_or_else(|_| "Unknown".to_string())
     }
+
+    fn id<'a>(&'a self) -> Option<impl AsRef<str> + 'a> {
+        self.badge
+            .member_id(self.id as u16)
+            .ok()
+            .filter(|id| !id.is_empty())
+    }
+
+    fn display_name<'a>(&'a self) -> Option<impl AsRef<str> + 'a> {
+        self.badge
+            .member_display_name(self.id as u16)
+            .ok()
+            .filter(|name| !name.is_empty())
+    }
 }
@@ -13,6 +13,6 @@ fn main() {
     println!("Member pronouns: {}", usb.member_pronouns(0).unwrap());
 
     let mut state = usb.get_state().unwrap();
-    state.change(Button::C, 2);
+    state.change(Button::C, &usb);
     usb.set_state(&state).unwrap();
 }
@@ -95,10 +95,17 @@ fn create_system() -> SystemVec {
     system.members.push(sysbadge::system::MemberStrings {
         name: "Myriad".to_string(),
         pronouns: "they/them".to_string(),
+        ..Default::default()
     });
     system.members.push(sysbadge::system::MemberStrings {
         name: "Tester T. Testington".to_string(),
         pronouns: "".to_string(),
+        ..Default::default()
+    });
+    system.members.push(sysbadge::system::MemberStrings {
+        name: "Zoë Ренат Ελένη".to_string(),
+        pronouns: "she/они".to_string(),
+        ..Default::default()
     });
     system
 }
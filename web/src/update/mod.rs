@@ -1,7 +1,7 @@
-use pkrs::client::PkClient;
-use pkrs::model::PkId;
 use std::mem::MaybeUninit;
+use std::sync::{Arc, Mutex};
 use std::{mem, ptr};
+use sysbadge::system::downloaders::{HttpClient, PkDownloader, Progress};
 use sysbadge::system::SystemVec;
 use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::{JsCast, JsValue};
@@ -24,7 +24,9 @@ pub(crate) fn register(document: &Document) -> Result<(), JsValue> {
         {
             let closur = Closure::wrap(Box::new(move || {
                 spawn_local(async move {
-                    let _system = update().await.unwrap();
+                    if let Err(err) = update().await {
+                        show_error(&err);
+                    }
                 });
             }) as Box<dyn FnMut()>);
 
@@ -43,8 +45,10 @@ pub(crate) fn register(document: &Document) -> Result<(), JsValue> {
                     if let Some(sys) = unsafe { &SYSTEM } {
                         download_uf2(sys);
                     } else {
-                        let system = update().await.unwrap();
-                        download_uf2(&system);
+                        match update().await {
+                            Ok(system) => download_uf2(system),
+                            Err(err) => show_error(&err),
+                        }
                     }
                 });
             }) as Box<dyn FnMut()>);
@@ -71,25 +75,113 @@ pub(crate) fn register(document: &Document) -> Result<(), JsValue> {
 
             closure.forget();
         }
+        // Export file
+        {
+            let closure = Closure::wrap(Box::new(move || {
+                spawn_local(async move {
+                    if let Err(err) = import().await {
+                        show_error(&err);
+                    }
+                });
+            }) as Box<dyn FnMut()>);
+
+            document
+                .get_element_by_id("_sysbadge-updater-file")
+                .unwrap()
+                .add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())
+                .unwrap();
+
+            closure.forget();
+        }
     }
 
     #[cfg(feature = "badge")]
     {
         spawn_local(async {
-            let id = PkId("exmpl".to_string());
-            let sys = System::get(id).await.unwrap();
-
-            #[cfg(feature = "badge")]
-            sys.set_system();
+            match System::get("exmpl", None, true).await {
+                Ok(sys) => sys.set_system(),
+                Err(err) => web_sys::console::error_1(&err),
+            }
         })
     }
 
     Ok(())
 }
 
+/// Tells the user why loading a system failed.
+fn show_error(err: &JsValue) {
+    let message = err
+        .as_string()
+        .unwrap_or_else(|| "Failed to load the system".to_string());
+    window().unwrap().alert_with_message(&message).unwrap();
+}
+
+/// Shows how far the download is, retries can take a while.
+fn show_progress(progress: &Progress) {
+    match progress {
+        Progress::Request { .. } => set_status("Downloading..."),
+        Progress::Retry {
+            url,
+            attempt,
+            delay_ms,
+            ..
+        } => set_status(&format!(
+            "Request to {} failed, retry {} in {}s",
+            url,
+            attempt,
+            (delay_ms + 999) / 1000
+        )),
+        Progress::Avatars { done, total } => {
+            set_status(&format!("Downloaded {}/{} avatars", done, total))
+        }
+        // collected by `client`, shown once the download is done
+        Progress::Skipped { .. } => {}
+    }
+}
+
+/// Client showing the progress, the private members left out are added to `skipped`.
+fn client(skipped: &Arc<Mutex<Vec<String>>>) -> HttpClient {
+    let skipped = skipped.clone();
+    HttpClient::new("sysbadge wasm updater").with_progress(move |progress| {
+        if let Progress::Skipped { names, .. } = progress {
+            skipped.lock().unwrap().extend_from_slice(names);
+        }
+        show_progress(progress);
+    })
+}
+
+/// Replaces the progress with the private members left out, the token and exports include them.
+fn show_skipped(skipped: &Mutex<Vec<String>>) {
+    let skipped = skipped.lock().unwrap();
+    if skipped.is_empty() {
+        set_status("");
+    } else {
+        set_status(&format!("Left out private members: {}", skipped.join(", ")));
+    }
+}
+
+fn set_status(status: &str) {
+    // looked up on every call, the callback has to be `Send`
+    let element = window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id("_sysbadge-updater-status"));
+    if let Some(element) = element {
+        element.set_text_content(Some(status));
+    }
+}
+
 fn download_uf2(system: &System) {
     let offset = RP2040_ROM_ADDR + RP2040_DATA_ADDR;
-    let vec = system.get_system().get_uf2(offset);
+    let vec = match system.get_system().get_uf2(offset) {
+        Ok(vec) => vec,
+        Err(err) => {
+            window()
+                .unwrap()
+                .alert_with_message(&err.to_string())
+                .unwrap();
+            return;
+        }
+    };
 
     let download_name = format!("{}.uf2", system.system.name);
 
@@ -128,8 +220,55 @@ async fn update() -> Result<&'static System, JsValue> {
         .dyn_into::<HtmlInputElement>()
         .unwrap();
 
-    let id = PkId(input.value());
-    let sys = System::get(id).await?;
+    let token = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .get_element_by_id("_sysbadge-updater-token")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+        .value();
+    let token = Some(token.trim()).filter(|token| !token.is_empty());
+
+    let avatars = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .get_element_by_id("_sysbadge-updater-avatars")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+        .checked();
+
+    let sys = System::get(&input.value(), token, avatars).await?;
+
+    #[cfg(feature = "badge")]
+    sys.set_system();
+
+    unsafe { SYSTEM = Some(sys) }
+
+    Ok(unsafe { SYSTEM.as_ref().unwrap_unchecked() })
+}
+
+/// Reads the system from the `pk;export` file picked by the user.
+async fn import() -> Result<&'static System, JsValue> {
+    let input = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .get_element_by_id("_sysbadge-updater-file")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap();
+    let file = input
+        .files()
+        .and_then(|files| files.get(0))
+        .ok_or_else(|| JsValue::from_str("No file selected"))?;
+
+    let buffer = wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await?;
+    let data = js_sys::Uint8Array::new(&buffer).to_vec();
+    let sys = System::import(&data)?;
 
     #[cfg(feature = "badge")]
     sys.set_system();
@@ -144,11 +283,32 @@ struct System {
 }
 
 impl System {
-    async fn get(id: PkId) -> Result<Self, JsValue> {
-        let mut updater = sysbadge::system::downloaders::PkDownloader::new();
-        updater.client.user_agent = "sysbadge wasm updater".to_string();
+    async fn get(id: &str, token: Option<&str>, avatars: bool) -> Result<Self, JsValue> {
+        let skipped = Arc::default();
+        let mut updater = PkDownloader::new().with_client(client(&skipped));
+        if let Some(token) = token {
+            updater = updater.with_token(token);
+        }
+        if !avatars {
+            updater = updater.without_avatars();
+        }
+
+        let system = updater.get(id).await;
+        show_skipped(&skipped);
+        let mut system = system
+            .map_err(|err| JsValue::from_str(&format!("Failed to download {}: {}", id, err)))?;
+        system.sort_members();
+
+        Ok(Self { system })
+    }
 
-        let mut system = updater.get(&id.0).await?;
+    fn import(data: &[u8]) -> Result<Self, JsValue> {
+        let skipped = Arc::default();
+        let mut system = PkDownloader::new()
+            .with_client(client(&skipped))
+            .import(data)
+            .map_err(|err| JsValue::from_str(&format!("Invalid export file: {}", err)))?;
+        show_skipped(&skipped);
         system.sort_members();
 
         Ok(Self { system })